@@ -0,0 +1,187 @@
+use core::fmt::Debug;
+use core::time::Duration;
+
+use embedded_hal_async::delay::DelayNs;
+use rand_core::RngCore;
+
+use crate::device::class_a::TransmitResult;
+use crate::device::error::DeviceError;
+use crate::device::{join_attempts, ClassA, ClassC, Credentials, Device, JoinAttempt, JoinResult};
+use crate::lorawan::{DevNonce, JoinRequest, Settings, Uplink, JOIN_ACCEPT_DELAY, MAX_PACKET_SIZE};
+use crate::radio::{AsyncRadio, ChannelMask, Clock, NoHooks, RegionalParameters};
+
+impl<RXTX, TIM, RNG, ERR> Device<RXTX, TIM, RNG, ERR, Credentials>
+where
+    RXTX: AsyncRadio<Error = ERR>,
+    TIM: DelayNs + Clock,
+    RNG: RngCore,
+    ERR: Debug,
+{
+    /// The async version of [Device::join].
+    pub async fn join_async<R: RegionalParameters + Clone>(
+        self,
+        region: R,
+    ) -> JoinResult<RXTX, TIM, RNG, ERR, R, NoHooks> {
+        let join_channels = region.join_channel_mask();
+        let channels = region.default_channel_mask();
+        self.join_on_async(region, join_channels, channels).await
+    }
+
+    /// The async version of [Device::join_scan].
+    pub async fn join_scan_async<R: RegionalParameters + Clone>(
+        self,
+        region: R,
+    ) -> JoinResult<RXTX, TIM, RNG, ERR, R, NoHooks> {
+        self.join_each_async(join_attempts(core::slice::from_ref(&region)))
+            .await
+    }
+
+    /// The async version of [Device::join_detect].
+    pub async fn join_detect_async<R: RegionalParameters + Clone>(
+        self,
+        regions: &[R],
+    ) -> JoinResult<RXTX, TIM, RNG, ERR, R, NoHooks> {
+        self.join_each_async(join_attempts(regions)).await
+    }
+
+    /// The async version of [Device::join_with_channels].
+    pub async fn join_with_channels_async<R: RegionalParameters + Clone>(
+        self,
+        region: R,
+        channels: ChannelMask,
+    ) -> JoinResult<RXTX, TIM, RNG, ERR, R, NoHooks> {
+        self.join_on_async(region, channels.clone(), channels).await
+    }
+
+    /// The async version of [Device::join_each].
+    async fn join_each_async<R: RegionalParameters + Clone>(
+        mut self,
+        attempts: impl Iterator<Item = JoinAttempt<R>>,
+    ) -> JoinResult<RXTX, TIM, RNG, ERR, R, NoHooks> {
+        for (region, join_channels, channels) in attempts {
+            match self.join_on_async(region, join_channels, channels).await {
+                Err(DeviceError::Join(device)) => self = device,
+                result => return result,
+            }
+        }
+
+        Err(DeviceError::Join(self))
+    }
+
+    /// The async version of [Device::join_on].
+    async fn join_on_async<R: RegionalParameters + Clone>(
+        mut self,
+        region: R,
+        join_channels: ChannelMask,
+        channels: ChannelMask,
+    ) -> JoinResult<RXTX, TIM, RNG, ERR, R, NoHooks> {
+        let dev_nonce = DevNonce::new(self.radio.random_nonce()?);
+
+        let join_request = JoinRequest::new(&self.state, &dev_nonce);
+        let mut buf = [0; MAX_PACKET_SIZE];
+
+        let mut settings = Settings::default_for(region.clone());
+        settings.set_channel_mask(join_channels);
+
+        let response = self
+            .radio
+            .lorawan_transmit_delayed_async(
+                join_request.payload(),
+                &mut buf,
+                region.join_data_rate(),
+                region.downlink_dwell_time(),
+                JOIN_ACCEPT_DELAY,
+                &settings,
+            )
+            .await;
+        match response {
+            Ok(response) => {
+                let response = response.map(|(n, _)| &mut buf[..n]);
+                self.accept(response, &dev_nonce, region, channels)
+            }
+            Err(error) => self.join_failed(error),
+        }
+    }
+}
+
+impl<RXTX, TIM, RNG, ERR, R> ClassA<RXTX, TIM, RNG, ERR, R>
+where
+    RXTX: AsyncRadio<Error = ERR>,
+    TIM: DelayNs + Clock,
+    RNG: RngCore,
+    ERR: Debug,
+    R: RegionalParameters,
+{
+    /// The async version of [ClassA::transmit].
+    pub async fn transmit_async(
+        &mut self,
+        tx: &[u8],
+        rx: &mut [u8],
+    ) -> TransmitResult<RXTX, TIM, RNG, ERR, NoHooks> {
+        let device: &mut Device<_, _, _, _, _, _> = self;
+        let uplink = Uplink::new(tx, 1, &mut device.state)?;
+        let downlink = device
+            .radio
+            .lorawan_transmit_async(
+                uplink.as_bytes(),
+                rx,
+                uplink.data_rate(),
+                device.state.downlink_dwell_time(),
+                device.state.settings(),
+            )
+            .await?;
+        device.state.mac_answers_mut().clear();
+
+        device.decode(downlink, rx)
+    }
+}
+
+impl<RXTX, TIM, RNG, ERR, R> ClassC<RXTX, TIM, RNG, ERR, R>
+where
+    RXTX: AsyncRadio<Error = ERR>,
+    TIM: DelayNs + Clock,
+    RNG: RngCore,
+    ERR: Debug,
+    R: RegionalParameters,
+{
+    /// The async version of [ClassC::transmit]. The radio listens on RX2 until the end of the RX2
+    /// window, and afterwards only during [receive_async].
+    ///
+    /// [receive_async]: ClassC::receive_async
+    pub async fn transmit_async(
+        &mut self,
+        tx: &[u8],
+        rx: &mut [u8],
+    ) -> TransmitResult<RXTX, TIM, RNG, ERR, NoHooks> {
+        let device: &mut Device<_, _, _, _, _, _> = self;
+        let uplink = Uplink::new(tx, 1, &mut device.state)?;
+        let downlink = device
+            .radio
+            .lorawan_transmit_class_c_async(
+                uplink.as_bytes(),
+                rx,
+                uplink.data_rate(),
+                device.state.downlink_dwell_time(),
+                device.state.settings(),
+            )
+            .await?;
+        device.state.mac_answers_mut().clear();
+
+        device.decode(downlink, rx)
+    }
+
+    /// The async version of [ClassC::receive]. The radio only listens on RX2 while this runs.
+    pub async fn receive_async(
+        &mut self,
+        rx: &mut [u8],
+        timeout: Duration,
+    ) -> TransmitResult<RXTX, TIM, RNG, ERR, NoHooks> {
+        let device: &mut Device<_, _, _, _, _, _> = self;
+        let downlink = device
+            .radio
+            .lorawan_listen_async(rx, timeout, device.state.settings())
+            .await?;
+
+        device.decode(downlink, rx)
+    }
+}
@@ -0,0 +1,307 @@
+use core::fmt::Debug;
+use core::ops::{Deref, DerefMut};
+use core::time::Duration;
+
+use embedded_hal::blocking::delay::DelayUs;
+use radio::{Busy, Channel, Receive, Transmit};
+use rand_core::RngCore;
+
+use crate::device::class_a::TransmitResult;
+use crate::device::error::DeviceError;
+use crate::device::{ClassA, Device, DeviceState};
+use crate::lorawan::{
+    ping_offset, Beacon, Uplink, BEACONLESS_PERIOD, BEACON_PERIOD, BEACON_RESERVED,
+    MAX_PACKET_SIZE, PING_SLOT, PING_SLOTS,
+};
+use crate::radio::{
+    Clock, DataRate, LoRaInfo, NoHooks, RadioChannel, RadioError, RadioHooks, RegionalParameters,
+};
+
+/// The synchronization of a class B device with the beacon.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+struct BeaconTracking {
+    /// The GPS time at the start of the current beacon period.
+    time: u32,
+    /// The start of the current beacon period, according to the clock of the device.
+    start: Duration,
+    /// The start of the beacon period of the last beacon that was received.
+    synced: Duration,
+}
+
+#[derive(Debug)]
+pub struct ClassB<RXTX, TIM, RNG, ERR, R, H = NoHooks>(
+    Device<RXTX, TIM, RNG, ERR, DeviceState<R>, H>,
+    Option<BeaconTracking>,
+);
+
+impl<RXTX, TIM, RNG, ERR, INFO, CH, R, H> ClassB<RXTX, TIM, RNG, ERR, R, H>
+where
+    RXTX: Receive<Error = ERR, Info = INFO>,
+    RXTX: Transmit<Error = ERR>,
+    RXTX: Channel<Channel = CH, Error = ERR>,
+    RXTX: Busy<Error = ERR>,
+    TIM: DelayUs<u32> + Clock,
+    RNG: RngCore,
+    ERR: Debug,
+    INFO: Into<LoRaInfo>,
+    CH: RadioChannel,
+    H: RadioHooks<RXTX>,
+    R: RegionalParameters,
+{
+    /// Transmits `tx` and waits for an optional response in RX1 or RX2, like [ClassA::transmit].
+    /// Once the beacon is acquired, uplinks tell the network that it may use the ping slots.
+    pub fn transmit(&mut self, tx: &[u8], rx: &mut [u8]) -> TransmitResult<RXTX, TIM, RNG, ERR, H> {
+        let uplink = Uplink::new(tx, 1, &mut self.state)?;
+        let downlink = self.0.radio.lorawan_transmit(
+            uplink.as_bytes(),
+            rx,
+            uplink.data_rate(),
+            self.0.state.downlink_dwell_time(),
+            self.0.state.settings(),
+        )?;
+        self.0.state.mac_answers_mut().clear();
+
+        self.0.decode(downlink, rx)
+    }
+
+    /// Listens for the beacon until it is found, which can take several beacon periods if the
+    /// beacon hops between frequencies. Returns [DeviceError::BeaconLost] if it is not found.
+    pub fn acquire_beacon(&mut self) -> Result<Beacon, DeviceError<RXTX, TIM, RNG, ERR, H>> {
+        let region = self.0.state.region();
+        let params = region.beacon();
+        let data_rate = Self::data_rate(region, params.data_rate())?;
+        // When the beacon hops, it is sent on the first frequency once every cycle
+        let frequency = self
+            .0
+            .state
+            .beacon_frequency()
+            .unwrap_or(params.frequencies()[0]);
+        let channel = data_rate
+            .beacon_channel(frequency, params.size())
+            .ok_or(RadioError::UnsupportedDataRate)?;
+        let cycles = match self.0.state.beacon_frequency() {
+            Some(_) => 1,
+            None => params.frequencies().len() as u32,
+        };
+        let deadline = self.now() + BEACON_PERIOD * cycles;
+
+        #[cfg(feature = "defmt")]
+        defmt::trace!("acquiring beacon");
+        let mut buf = [0; MAX_PACKET_SIZE];
+        loop {
+            let timeout = deadline.saturating_sub(self.now());
+            let response = self
+                .0
+                .radio
+                .lorawan_receive(&mut buf, channel.clone(), timeout)?;
+            let received = self.now();
+            let params = self.0.state.region().beacon();
+            match response.map(|(n, _)| Beacon::from_data(&buf[..n], params)) {
+                None => return Err(DeviceError::BeaconLost),
+                Some(Ok(beacon)) => {
+                    self.synchronize(&beacon, received)?;
+                    return Ok(beacon);
+                }
+                // Other downlinks or corrupted beacons
+                Some(Err(_)) => {}
+            }
+        }
+    }
+
+    /// Sleeps until the next ping slot and waits for a downlink in it, storing it in `rx` and
+    /// returning the size and packet information if applicable. Beacons in between are received to
+    /// stay synchronized, and the beacon is acquired first if necessary.
+    ///
+    /// If no beacon has been received for [BEACONLESS_PERIOD], the device stops using ping slots
+    /// and returns [DeviceError::BeaconLost]. It can then acquire the beacon again, or fall back to
+    /// class A with [into_class_a].
+    ///
+    /// [into_class_a]: ClassB::into_class_a
+    pub fn receive(&mut self, rx: &mut [u8]) -> TransmitResult<RXTX, TIM, RNG, ERR, H> {
+        if self.1.is_none() {
+            self.acquire_beacon()?;
+        }
+
+        loop {
+            let tracking = self.1.ok_or(DeviceError::BeaconLost)?;
+            if let Some(slot) = self.next_ping_slot(&tracking) {
+                let region = self.0.state.region();
+                let params = region.beacon();
+                let dr = self.0.state.ping_slot_dr().unwrap_or(params.data_rate());
+                let data_rate = Self::data_rate(region, dr)?;
+                let dev_addr = u32::from_le_bytes(*self.0.state.session().dev_addr().as_bytes());
+                let frequency = self
+                    .0
+                    .state
+                    .ping_slot_frequency()
+                    .unwrap_or_else(|| params.ping_slot_frequency(tracking.time, dev_addr));
+                let channel = region.channel(&data_rate, frequency);
+
+                #[cfg(feature = "defmt")]
+                defmt::trace!("receiving in ping slot");
+                let downlink = self.0.radio.lorawan_receive_at(
+                    rx,
+                    channel,
+                    &data_rate,
+                    slot,
+                    slot - tracking.synced,
+                )?;
+                return self.0.decode(downlink, rx);
+            }
+
+            self.track_beacon(&tracking)?;
+        }
+    }
+
+    /// Whether the device is synchronized to the beacon, with or without having received it
+    /// recently.
+    pub fn is_synchronized(&self) -> bool {
+        self.1.is_some()
+    }
+
+    /// Requests the network to open a ping slot every 2^`periodicity` seconds, from 0 to 7. It is
+    /// sent with the next uplink, and takes effect once the network answers.
+    pub fn set_ping_slot_periodicity(&mut self, periodicity: u8) {
+        self.0.state.request_ping_slot_periodicity(periodicity);
+    }
+
+    /// Configures this device to have class A behavior again. The network is told in the next
+    /// uplink to stop using the ping slots.
+    pub fn into_class_a(mut self) -> ClassA<RXTX, TIM, RNG, ERR, R, H> {
+        self.0.state.set_class_b(false);
+        self.0.into()
+    }
+
+    /// Returns the start of the next ping slot in the current beacon period that has not opened
+    /// yet, or `None` if there are none left.
+    fn next_ping_slot(&mut self, tracking: &BeaconTracking) -> Option<Duration> {
+        let now = self.now();
+        let ping_period = 1 << (5 + self.0.state.ping_slot_periodicity());
+        let offset = ping_offset(
+            tracking.time,
+            self.0.state.session().dev_addr(),
+            ping_period,
+        );
+
+        // Slots that are about to start cannot be opened in time
+        (offset..PING_SLOTS)
+            .step_by(ping_period as usize)
+            .map(|slot| tracking.start + BEACON_RESERVED + PING_SLOT * slot)
+            .find(|&start| start > now + PING_SLOT)
+    }
+
+    /// Waits for the beacon at the start of the next beacon period. If it is not received, the
+    /// device keeps its timing, until it has missed beacons for [BEACONLESS_PERIOD].
+    fn track_beacon(
+        &mut self,
+        tracking: &BeaconTracking,
+    ) -> Result<(), DeviceError<RXTX, TIM, RNG, ERR, H>> {
+        let time = tracking.time.wrapping_add(BEACON_PERIOD.as_secs() as u32);
+        let start = tracking.start + BEACON_PERIOD;
+
+        let region = self.0.state.region();
+        let params = region.beacon();
+        let data_rate = Self::data_rate(region, params.data_rate())?;
+        let frequency = self
+            .0
+            .state
+            .beacon_frequency()
+            .unwrap_or_else(|| params.frequency(time));
+        let channel = data_rate
+            .beacon_channel(frequency, params.size())
+            .ok_or(RadioError::UnsupportedDataRate)?;
+
+        #[cfg(feature = "defmt")]
+        defmt::trace!("receiving beacon");
+        let mut buf = [0; MAX_PACKET_SIZE];
+        let response = self.0.radio.lorawan_receive_at(
+            &mut buf,
+            channel,
+            &data_rate,
+            start,
+            start - tracking.synced,
+        )?;
+        let received = self.now();
+
+        let beacon = response
+            .and_then(|(n, _)| Beacon::from_data(&buf[..n], self.0.state.region().beacon()).ok());
+        match beacon {
+            Some(beacon) => self.synchronize(&beacon, received),
+            None if start - tracking.synced >= BEACONLESS_PERIOD => {
+                #[cfg(feature = "defmt")]
+                defmt::trace!("beacon lost, falling back to class A");
+                self.1 = None;
+                self.0.state.set_class_b(false);
+                Err(DeviceError::BeaconLost)
+            }
+            None => {
+                #[cfg(feature = "defmt")]
+                defmt::trace!("beacon missed");
+                self.1 = Some(BeaconTracking {
+                    time,
+                    start,
+                    synced: tracking.synced,
+                });
+                Ok(())
+            }
+        }
+    }
+
+    /// Aligns the beacon periods with `beacon`, which was received at `received`.
+    fn synchronize(
+        &mut self,
+        beacon: &Beacon,
+        received: Duration,
+    ) -> Result<(), DeviceError<RXTX, TIM, RNG, ERR, H>> {
+        let region = self.0.state.region();
+        let params = region.beacon();
+        let air_time = Self::data_rate(region, params.data_rate())?
+            .beacon_air_time(params.size())
+            .ok_or(RadioError::UnsupportedDataRate)?;
+        let start = received.saturating_sub(air_time);
+
+        #[cfg(feature = "defmt")]
+        defmt::trace!("synchronized to beacon");
+        self.1 = Some(BeaconTracking {
+            time: beacon.time(),
+            start,
+            synced: start,
+        });
+        self.0.state.set_class_b(true);
+        Ok(())
+    }
+
+    fn data_rate(region: &R, dr: usize) -> Result<DataRate, RadioError<ERR>> {
+        region
+            .data_rate(dr)
+            .cloned()
+            .ok_or(RadioError::UnsupportedDataRate)
+    }
+
+    fn now(&mut self) -> Duration {
+        self.0.radio.as_mut_tim().now()
+    }
+}
+
+impl<RXTX, TIM, RNG, ERR, R, H> From<Device<RXTX, TIM, RNG, ERR, DeviceState<R>, H>>
+    for ClassB<RXTX, TIM, RNG, ERR, R, H>
+{
+    fn from(device: Device<RXTX, TIM, RNG, ERR, DeviceState<R>, H>) -> Self {
+        ClassB(device, None)
+    }
+}
+
+impl<RXTX, TIM, RNG, ERR, R, H> Deref for ClassB<RXTX, TIM, RNG, ERR, R, H> {
+    type Target = Device<RXTX, TIM, RNG, ERR, DeviceState<R>, H>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<RXTX, TIM, RNG, ERR, R, H> DerefMut for ClassB<RXTX, TIM, RNG, ERR, R, H> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
@@ -0,0 +1,94 @@
+use core::fmt::Debug;
+use core::ops::{Deref, DerefMut};
+use core::time::Duration;
+
+use embedded_hal::blocking::delay::DelayUs;
+use radio::{Busy, Channel, Receive, Transmit};
+use rand_core::RngCore;
+
+use crate::device::class_a::TransmitResult;
+use crate::device::{ClassA, Device, DeviceClass, DeviceState};
+use crate::lorawan::Uplink;
+use crate::radio::{Clock, LoRaInfo, NoHooks, RadioChannel, RadioHooks, RegionalParameters};
+
+#[derive(Debug)]
+pub struct ClassC<RXTX, TIM, RNG, ERR, R, H = NoHooks>(
+    Device<RXTX, TIM, RNG, ERR, DeviceState<R>, H>,
+);
+
+impl<RXTX, TIM, RNG, ERR, INFO, CH, R, H> ClassC<RXTX, TIM, RNG, ERR, R, H>
+where
+    RXTX: Receive<Error = ERR, Info = INFO>,
+    RXTX: Transmit<Error = ERR>,
+    RXTX: Channel<Channel = CH, Error = ERR>,
+    RXTX: Busy<Error = ERR>,
+    TIM: DelayUs<u32> + Clock,
+    RNG: RngCore,
+    ERR: Debug,
+    INFO: Into<LoRaInfo>,
+    CH: RadioChannel,
+    H: RadioHooks<RXTX>,
+    R: RegionalParameters,
+{
+    /// Transmits `tx` and waits for an optional response, storing it in `rx` and returning the size
+    /// and packet information if applicable. Unlike [ClassA::transmit], the radio listens on RX2
+    /// instead of sleeping between the receive windows, and keeps listening afterwards.
+    pub fn transmit(&mut self, tx: &[u8], rx: &mut [u8]) -> TransmitResult<RXTX, TIM, RNG, ERR, H> {
+        let uplink = Uplink::new(tx, 1, &mut self.state)?;
+        let downlink = self.0.radio.lorawan_transmit_class_c(
+            uplink.as_bytes(),
+            rx,
+            uplink.data_rate(),
+            self.0.state.downlink_dwell_time(),
+            self.0.state.settings(),
+        )?;
+        self.0.state.mac_answers_mut().clear();
+
+        self.0.decode(downlink, rx)
+    }
+
+    /// Waits up to `timeout` for a downlink on RX2, storing it in `rx` and returning the size and
+    /// packet information if applicable. The radio keeps listening in between calls, so this can
+    /// be polled with a zero `timeout`.
+    pub fn receive(
+        &mut self,
+        rx: &mut [u8],
+        timeout: Duration,
+    ) -> TransmitResult<RXTX, TIM, RNG, ERR, H> {
+        let downlink = self
+            .0
+            .radio
+            .lorawan_listen(rx, timeout, self.0.state.settings())?;
+
+        self.0.decode(downlink, rx)
+    }
+
+    /// Configures this device to have class A behavior again. The switch is announced to the
+    /// network in the next uplink if it implements LoRaWAN 1.1.
+    pub fn into_class_a(mut self) -> ClassA<RXTX, TIM, RNG, ERR, R, H> {
+        self.0.state.announce_device_mode(DeviceClass::A);
+        self.0.into()
+    }
+}
+
+impl<RXTX, TIM, RNG, ERR, R, H> From<Device<RXTX, TIM, RNG, ERR, DeviceState<R>, H>>
+    for ClassC<RXTX, TIM, RNG, ERR, R, H>
+{
+    fn from(device: Device<RXTX, TIM, RNG, ERR, DeviceState<R>, H>) -> Self {
+        ClassC(device)
+    }
+}
+
+impl<RXTX, TIM, RNG, ERR, R, H> Deref for ClassC<RXTX, TIM, RNG, ERR, R, H> {
+    type Target = Device<RXTX, TIM, RNG, ERR, DeviceState<R>, H>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<RXTX, TIM, RNG, ERR, R, H> DerefMut for ClassC<RXTX, TIM, RNG, ERR, R, H> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
@@ -1,25 +1,27 @@
 use crate::device::{Credentials, Device};
 use crate::lorawan::PacketError;
-use crate::radio::RadioError;
+use crate::radio::{NoHooks, RadioError};
 
 /// Represents errors that can occur when using the device for LoRaWAN transmission.
 #[derive(Debug)]
-pub enum DeviceError<RXTX, TIM, RNG, ERR> {
+pub enum DeviceError<RXTX, TIM, RNG, ERR, H = NoHooks> {
     /// The device failed to join a network.
-    Join(Device<RXTX, TIM, RNG, ERR, Credentials>),
+    Join(Device<RXTX, TIM, RNG, ERR, Credentials, H>),
     /// Something went wrong with parsing or generating LoRaWAN packets.
     Packet(PacketError),
     /// Something went wrong with the hardware.
     Radio(RadioError<ERR>),
+    /// A class B device did not find the beacon, or has not received it for too long.
+    BeaconLost,
 }
 
-impl<RXTX, TIM, RNG, ERR> From<RadioError<ERR>> for DeviceError<RXTX, TIM, RNG, ERR> {
+impl<RXTX, TIM, RNG, ERR, H> From<RadioError<ERR>> for DeviceError<RXTX, TIM, RNG, ERR, H> {
     fn from(e: RadioError<ERR>) -> Self {
         DeviceError::Radio(e)
     }
 }
 
-impl<RXTX, TIM, RNG, ERR> From<PacketError> for DeviceError<RXTX, TIM, RNG, ERR> {
+impl<RXTX, TIM, RNG, ERR, H> From<PacketError> for DeviceError<RXTX, TIM, RNG, ERR, H> {
     fn from(e: PacketError) -> Self {
         DeviceError::Packet(e)
     }
@@ -0,0 +1,585 @@
+use core::time::Duration;
+
+use rand_core::RngCore;
+
+use crate::device::{Credentials, DeviceState, Session};
+use crate::lorawan::{
+    DevNonce, Downlink, JoinAccept, JoinRequest, PacketError, Settings, Uplink, JOIN_ACCEPT_DELAY,
+    MAX_PACKET_SIZE, NEXT_DELAY,
+};
+use crate::radio::{rx_windows, ChannelConfig, LoRaInfo, RegionalParameters, RxWindow};
+
+/// Something that happened to the radio or the timer, which the application reports to a [Mac].
+#[derive(Debug)]
+pub enum Event<'a> {
+    /// The radio finished transmitting, at the given time on the application's clock.
+    TxDone(Duration),
+    /// The radio received a message, which is stored in the buffer. If it is a downlink for this
+    /// device, its payload is decrypted in place.
+    RxDone(&'a mut [u8], LoRaInfo),
+    /// The radio did not detect a message within the timeout of the receive window.
+    RxTimeout,
+    /// The timer armed by [Action::ArmTimer] fired.
+    TimerFired,
+}
+
+/// What the application must do next, in response to an [Event].
+#[derive(Debug)]
+pub enum Action {
+    /// Configure the radio for `channel` and an EIRP of `eirp` dBm, and transmit [Mac::uplink],
+    /// then report [Event::TxDone].
+    Transmit { channel: ChannelConfig, eirp: i8 },
+    /// Arm a timer to fire at the given time on the application's clock, then report
+    /// [Event::TimerFired]. Until then, the radio and MCU may sleep.
+    ArmTimer(Duration),
+    /// Configure the radio for `channel` and listen for a preamble for up to `timeout`, then report
+    /// [Event::RxDone] or [Event::RxTimeout].
+    Receive {
+        channel: ChannelConfig,
+        timeout: Duration,
+    },
+    /// There is nothing to do until the next event, so the radio and MCU may sleep.
+    Sleep,
+    /// The device joined a network.
+    Joined,
+    /// The uplink is complete. If a downlink was received, this contains the size of its payload,
+    /// which was written to the start of the buffer of [Event::RxDone], and its packet
+    /// information.
+    Complete(Option<(usize, LoRaInfo)>),
+}
+
+/// The timing of the receive windows of the current uplink.
+#[derive(Debug)]
+struct Windows {
+    delay: Duration,
+    tx_done: Duration,
+    rx1: (ChannelConfig, RxWindow),
+    rx2: (ChannelConfig, RxWindow),
+}
+
+#[derive(Debug)]
+enum Phase {
+    Idle,
+    Transmitting(Windows),
+    WaitingForRx1(Windows),
+    Rx1(Windows),
+    WaitingForRx2(Windows),
+    Rx2,
+}
+
+#[derive(Debug)]
+enum Activation<R> {
+    Otaa {
+        credentials: Credentials,
+        region: R,
+        dev_nonce: Option<DevNonce>,
+    },
+    Joined(DeviceState<R>),
+}
+
+/// A non-blocking LoRaWAN class A device, for firmware that owns its interrupts and timers. The
+/// application starts an uplink or join, performs the returned [Action], and reports what
+/// happened as an [Event], until the action is [Action::Complete] or [Action::Joined].
+///
+/// All times are on the application's own monotonic clock. Listen-before-talk is left to the
+/// application, in regions that require it.
+#[derive(Debug)]
+pub struct Mac<R, RNG> {
+    activation: Activation<R>,
+    rng: RNG,
+    phase: Phase,
+    uplink: [u8; MAX_PACKET_SIZE],
+    uplink_len: usize,
+    clock_error: Duration,
+    clock_drift: u32,
+}
+
+impl<R, RNG> Mac<R, RNG>
+where
+    R: RegionalParameters + Clone,
+    RNG: RngCore,
+{
+    /// Creates a device that must join a network in `region` with [Mac::join].
+    pub fn new_otaa(credentials: Credentials, region: R, rng: RNG) -> Self {
+        Self::new(
+            Activation::Otaa {
+                credentials,
+                region,
+                dev_nonce: None,
+            },
+            rng,
+        )
+    }
+
+    /// Creates a device that has joined a network through Activation By Personalization.
+    pub fn new_abp(session: Session, region: R, rng: RNG) -> Self {
+        let state = DeviceState::new(session, Settings::default_for(region));
+        Self::new(Activation::Joined(state), rng)
+    }
+
+    fn new(activation: Activation<R>, rng: RNG) -> Self {
+        Mac {
+            activation,
+            rng,
+            phase: Phase::Idle,
+            uplink: [0; MAX_PACKET_SIZE],
+            uplink_len: 0,
+            clock_error: Duration::from_millis(3),
+            clock_drift: 0,
+        }
+    }
+
+    /// The state of the device, if it has joined a network.
+    pub fn state(&self) -> Option<&DeviceState<R>> {
+        match &self.activation {
+            Activation::Joined(state) => Some(state),
+            Activation::Otaa { .. } => None,
+        }
+    }
+
+    /// The state of the device, if it has joined a network, e.g. to change its data rate or
+    /// queue MAC commands for the next uplink.
+    pub fn state_mut(&mut self) -> Option<&mut DeviceState<R>> {
+        match &mut self.activation {
+            Activation::Joined(state) => Some(state),
+            Activation::Otaa { .. } => None,
+        }
+    }
+
+    /// Sets the largest error in the timing of events and timers, apart from clock drift, e.g.
+    /// the latency of interrupts. The receive windows are widened to make up for it.
+    pub fn set_clock_error(&mut self, clock_error: Duration) {
+        self.clock_error = clock_error;
+    }
+
+    /// Sets the largest drift of the application's clock, in parts per million.
+    pub fn set_clock_drift(&mut self, ppm: u32) {
+        self.clock_drift = ppm;
+    }
+
+    /// Whether an uplink or join is in progress.
+    pub fn is_busy(&self) -> bool {
+        !matches!(self.phase, Phase::Idle)
+    }
+
+    /// The uplink to transmit for [Action::Transmit].
+    pub fn uplink(&self) -> &[u8] {
+        &self.uplink[..self.uplink_len]
+    }
+
+    /// Starts joining the network with a join-request.
+    pub fn join(&mut self) -> Result<Action, MacError> {
+        if self.is_busy() {
+            return Err(MacError::Busy);
+        }
+
+        let mut nonce = [0; 2];
+        self.rng
+            .try_fill_bytes(&mut nonce)
+            .map_err(MacError::Random)?;
+
+        let (credentials, region, dev_nonce) = match &mut self.activation {
+            Activation::Otaa {
+                credentials,
+                region,
+                dev_nonce,
+            } => (credentials, region, dev_nonce),
+            Activation::Joined(_) => return Err(MacError::Joined),
+        };
+        let nonce = DevNonce::new(u16::from_le_bytes(nonce));
+        let join_request = JoinRequest::new(credentials, &nonce);
+        *dev_nonce = Some(nonce);
+
+        let payload = join_request.payload();
+        self.uplink[..payload.len()].copy_from_slice(payload);
+        self.uplink_len = payload.len();
+
+        let mut settings = Settings::default_for(region.clone());
+        settings.set_channel_mask(region.join_channel_mask());
+        let dr = region.join_data_rate();
+        let dwell_time = region.downlink_dwell_time();
+        self.start(&settings, dr, dwell_time, JOIN_ACCEPT_DELAY)
+    }
+
+    /// Starts an unconfirmed uplink of `payload` on port 1.
+    pub fn send(&mut self, payload: &[u8]) -> Result<Action, MacError> {
+        if self.is_busy() {
+            return Err(MacError::Busy);
+        }
+
+        let state = match &mut self.activation {
+            Activation::Joined(state) => state,
+            Activation::Otaa { .. } => return Err(MacError::NotJoined),
+        };
+        let uplink = Uplink::new(payload, 1, state)?;
+        self.uplink[..uplink.as_bytes().len()].copy_from_slice(uplink.as_bytes());
+        self.uplink_len = uplink.as_bytes().len();
+
+        let settings = state.settings().clone();
+        let dwell_time = state.downlink_dwell_time();
+        self.start(
+            &settings,
+            uplink.data_rate(),
+            dwell_time,
+            settings.rx_delay(),
+        )
+    }
+
+    /// Processes `event`, and returns what the application must do next. A message in RX2 that is
+    /// not a valid downlink for this device ends the uplink or join with an error, as no other
+    /// downlink can follow it.
+    pub fn handle(&mut self, event: Event) -> Result<Action, MacError> {
+        let phase = core::mem::replace(&mut self.phase, Phase::Idle);
+        match (phase, event) {
+            (Phase::Transmitting(mut windows), Event::TxDone(now)) => {
+                if let Activation::Joined(state) = &mut self.activation {
+                    state.mac_answers_mut().clear();
+                }
+                windows.tx_done = now;
+                let at = now + windows.delay.saturating_sub(windows.rx1.1.offset());
+                self.phase = Phase::WaitingForRx1(windows);
+                Ok(Action::ArmTimer(at))
+            }
+            (Phase::WaitingForRx1(windows), Event::TimerFired) => {
+                let action = Action::Receive {
+                    channel: windows.rx1.0.clone(),
+                    timeout: windows.rx1.1.timeout(),
+                };
+                self.phase = Phase::Rx1(windows);
+                Ok(action)
+            }
+            (Phase::Rx1(windows), Event::RxTimeout) => Ok(self.wait_for_rx2(windows)),
+            (Phase::WaitingForRx2(windows), Event::TimerFired) => {
+                let (channel, window) = windows.rx2;
+                self.phase = Phase::Rx2;
+                Ok(Action::Receive {
+                    channel,
+                    timeout: window.timeout(),
+                })
+            }
+            // A message that is not a valid downlink for this device, e.g. one for another device,
+            // does not end the uplink, as the downlink may still arrive in RX2
+            (Phase::Rx1(windows), Event::RxDone(buf, info)) => match self.receive(buf, info) {
+                Ok(action) => Ok(action),
+                Err(_) => Ok(self.wait_for_rx2(windows)),
+            },
+            (Phase::Rx2, Event::RxDone(buf, info)) => self.receive(buf, info),
+            (Phase::Rx2, Event::RxTimeout) => Ok(Action::Complete(None)),
+            // The event does not apply to the current phase, e.g. a stray interrupt
+            (phase, _) => {
+                self.phase = phase;
+                Ok(Action::Sleep)
+            }
+        }
+    }
+
+    /// Arms the timer for RX2, after nothing was received in RX1.
+    fn wait_for_rx2(&mut self, windows: Windows) -> Action {
+        let rx2_delay = windows.delay + NEXT_DELAY;
+        let at = windows.tx_done + rx2_delay.saturating_sub(windows.rx2.1.offset());
+        self.phase = Phase::WaitingForRx2(windows);
+        Action::ArmTimer(at)
+    }
+
+    /// Selects a channel for an uplink at `dr`, and prepares the receive windows that follow it.
+    fn start(
+        &mut self,
+        settings: &Settings<R>,
+        dr: usize,
+        dwell_time: bool,
+        delay: Duration,
+    ) -> Result<Action, MacError> {
+        let [(rx1_dr, rx1_window), (rx2_dr, rx2_window)] = rx_windows(
+            settings,
+            dr,
+            dwell_time,
+            delay,
+            self.clock_error,
+            self.clock_drift,
+        )
+        .ok_or(MacError::UnsupportedDataRate)?;
+
+        let mut noise = [0];
+        self.rng
+            .try_fill_bytes(&mut noise)
+            .map_err(MacError::Random)?;
+        let channel = settings
+            .select_channel(dr, noise[0] as usize)
+            .ok_or(MacError::NoChannel)?;
+
+        let tx_channel = settings.tx_channel(dr, channel);
+        let rx1_channel = settings.rx1_channel(rx1_dr, channel);
+        let rx2_channel = settings.region().rx2_channel(rx2_dr);
+        match (tx_channel, rx1_channel, rx2_channel) {
+            (Some(tx_channel), Some(rx1_channel), Some(rx2_channel)) => {
+                self.phase = Phase::Transmitting(Windows {
+                    delay,
+                    tx_done: Duration::ZERO,
+                    rx1: (rx1_channel, rx1_window),
+                    rx2: (rx2_channel, rx2_window),
+                });
+                Ok(Action::Transmit {
+                    channel: tx_channel,
+                    eirp: settings.max_eirp(),
+                })
+            }
+            _ => Err(MacError::UnsupportedDataRate),
+        }
+    }
+
+    /// Processes a message received in RX1 or RX2, which completes the uplink or join.
+    fn receive(&mut self, buf: &mut [u8], info: LoRaInfo) -> Result<Action, MacError> {
+        match &mut self.activation {
+            Activation::Joined(state) => {
+                let downlink = Downlink::from_data(buf, state)?;
+                let payload = downlink.as_bytes();
+                buf[..payload.len()].copy_from_slice(payload);
+                Ok(Action::Complete(Some((payload.len(), info))))
+            }
+            Activation::Otaa {
+                credentials,
+                region,
+                dev_nonce,
+            } => {
+                let dev_nonce = dev_nonce.as_ref().ok_or(MacError::NotJoined)?;
+                let channels = region.default_channel_mask();
+                let state = JoinAccept::from_data(buf)?.extract_state(
+                    credentials,
+                    dev_nonce,
+                    region.clone(),
+                    channels,
+                )?;
+                self.activation = Activation::Joined(state);
+
+                #[cfg(feature = "defmt")]
+                defmt::trace!("joined successfully");
+
+                Ok(Action::Joined)
+            }
+        }
+    }
+}
+
+/// Represents errors that can occur when using a [Mac].
+#[derive(Debug)]
+pub enum MacError {
+    /// Something went wrong with parsing or generating LoRaWAN packets.
+    Packet(PacketError),
+    /// Failed to generate a random number.
+    Random(rand_core::Error),
+    /// An uplink or join is already in progress.
+    Busy,
+    /// The device must join a network first.
+    NotJoined,
+    /// The device has already joined a network.
+    Joined,
+    /// The region can not use the data rate of the uplink or of one of its receive windows, e.g.
+    /// an RX1 data rate offset the region does not define.
+    UnsupportedDataRate,
+    /// None of the enabled channels supports the requested data rate.
+    NoChannel,
+}
+
+impl From<PacketError> for MacError {
+    fn from(e: PacketError) -> Self {
+        MacError::Packet(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lorawan::fixtures::{APP_KEY, JOIN_ACCEPT};
+    use crate::lorawan::{AppEui, AppKey, AppSKey, DevAddr, DevEui, NwkSKey};
+    use crate::radio::{AddedChannel, EU868};
+    use radio::modulation::lora::SpreadingFactor;
+
+    struct ZeroRng;
+
+    impl RngCore for ZeroRng {
+        fn next_u32(&mut self) -> u32 {
+            0
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            0
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            dest.fill(0);
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+
+    fn abp() -> Mac<EU868, ZeroRng> {
+        let session = Session::new(DevAddr::new(0x26011BDA), NwkSKey::new(1), AppSKey::new(2));
+        Mac::new_abp(session, EU868, ZeroRng)
+    }
+
+    fn otaa() -> Mac<EU868, ZeroRng> {
+        let credentials = Credentials::new(AppEui::new(0), DevEui::new(0), AppKey::new(APP_KEY));
+        Mac::new_otaa(credentials, EU868, ZeroRng)
+    }
+
+    /// Reports that the uplink was transmitted at time zero, and returns when each receive
+    /// window opens.
+    fn transmit(mac: &mut Mac<EU868, ZeroRng>, action: Action) -> Duration {
+        assert!(matches!(action, Action::Transmit { .. }));
+        match mac.handle(Event::TxDone(Duration::ZERO)).unwrap() {
+            Action::ArmTimer(at) => at,
+            action => panic!("unexpected {:?}", action),
+        }
+    }
+
+    fn open_window(mac: &mut Mac<EU868, ZeroRng>) {
+        let action = mac.handle(Event::TimerFired).unwrap();
+        assert!(matches!(action, Action::Receive { .. }), "{:?}", action);
+    }
+
+    fn rx2_at(action: Action) -> Duration {
+        match action {
+            Action::ArmTimer(at) => at,
+            action => panic!("unexpected {:?}", action),
+        }
+    }
+
+    #[test]
+    fn completes_without_downlink() {
+        let mut mac = abp();
+        let state = mac.state_mut().unwrap();
+        assert!(state.mac_answers_mut().push(0x02, &[]));
+
+        let action = mac.send(b"hi").unwrap();
+        assert!(!mac.state_mut().unwrap().mac_answers_mut().is_empty());
+        let rx1 = transmit(&mut mac, action);
+        assert!(mac.state_mut().unwrap().mac_answers_mut().is_empty());
+        assert!(rx1 < Duration::from_secs(1));
+
+        open_window(&mut mac);
+        let rx2 = rx2_at(mac.handle(Event::RxTimeout).unwrap());
+        assert_eq!(rx2, rx1 + Duration::from_secs(1));
+
+        open_window(&mut mac);
+        let action = mac.handle(Event::RxTimeout).unwrap();
+        assert!(matches!(action, Action::Complete(None)), "{:?}", action);
+        assert!(!mac.is_busy());
+    }
+
+    #[test]
+    fn waits_for_rx2_after_invalid_downlink() {
+        let mut mac = abp();
+        let action = mac.send(b"hi").unwrap();
+        let rx1 = transmit(&mut mac, action);
+
+        open_window(&mut mac);
+        let mut garbage = [0x60, 0x00, 0x00];
+        let rx2 = rx2_at(
+            mac.handle(Event::RxDone(&mut garbage, LoRaInfo::default()))
+                .unwrap(),
+        );
+        assert_eq!(rx2, rx1 + Duration::from_secs(1));
+
+        // An invalid message in RX2 ends the uplink with its error
+        open_window(&mut mac);
+        let mut garbage = [0x60, 0x00, 0x00];
+        let result = mac.handle(Event::RxDone(&mut garbage, LoRaInfo::default()));
+        assert!(matches!(result, Err(MacError::Packet(_))), "{:?}", result);
+        assert!(!mac.is_busy());
+    }
+
+    #[test]
+    fn sends_fsk_uplink_on_added_channel() {
+        // None of the default channels of EU868 supports DR7
+        let mut mac = abp();
+        mac.state_mut().unwrap().set_tx_dr(7);
+        assert!(matches!(
+            mac.send(b"hi"),
+            Err(MacError::Packet(PacketError::UnusableDataRate(7)))
+        ));
+
+        let channel = AddedChannel::new(867_100_000, 0, 7);
+        let settings = mac.state_mut().unwrap().settings_mut();
+        assert!(settings.set_added_channel(3, Some(channel)));
+        let action = mac.send(b"hi").unwrap();
+        assert!(
+            matches!(
+                &action,
+                Action::Transmit { channel: ChannelConfig::Fsk(fsk), .. } if fsk.freq_khz == 867_100
+            ),
+            "{:?}",
+            action
+        );
+        transmit(&mut mac, action);
+    }
+
+    #[test]
+    fn sends_lr_fhss_uplink_on_added_channel() {
+        let mut mac = abp();
+        let state = mac.state_mut().unwrap();
+        state.set_tx_dr(8);
+        let channel = AddedChannel::new(868_130_000, 8, 11);
+        assert!(state.settings_mut().set_added_channel(3, Some(channel)));
+
+        let action = mac.send(b"hi").unwrap();
+        assert!(
+            matches!(
+                &action,
+                Action::Transmit { channel: ChannelConfig::LrFhss(lr_fhss), .. }
+                    if lr_fhss.freq_khz == 868_130
+            ),
+            "{:?}",
+            action
+        );
+
+        // The network answers in RX1 with LoRa at DR1, on the uplink frequency
+        transmit(&mut mac, action);
+        let action = mac.handle(Event::TimerFired).unwrap();
+        assert!(
+            matches!(
+                &action,
+                Action::Receive { channel: ChannelConfig::LoRa(lora), .. }
+                    if lora.freq_khz == 868_130 && matches!(lora.sf, SpreadingFactor::Sf11)
+            ),
+            "{:?}",
+            action
+        );
+    }
+
+    #[test]
+    fn joins() {
+        let mut mac = otaa();
+        assert!(matches!(mac.send(b"hi"), Err(MacError::NotJoined)));
+
+        let action = mac.join().unwrap();
+        let rx1 = transmit(&mut mac, action);
+        assert!(rx1 < JOIN_ACCEPT_DELAY);
+
+        open_window(&mut mac);
+        let mut join_accept = JOIN_ACCEPT;
+        let action = mac
+            .handle(Event::RxDone(&mut join_accept, LoRaInfo::default()))
+            .unwrap();
+        assert!(matches!(action, Action::Joined), "{:?}", action);
+        assert_eq!(
+            mac.state().unwrap().session().dev_addr().as_bytes(),
+            &[0xDA, 0x1B, 0x01, 0x26]
+        );
+    }
+
+    #[test]
+    fn ignores_stray_events() {
+        let mut mac = abp();
+        let action = mac.handle(Event::TimerFired).unwrap();
+        assert!(matches!(action, Action::Sleep));
+        assert!(!mac.is_busy());
+
+        mac.send(b"hi").unwrap();
+        let action = mac.handle(Event::RxTimeout).unwrap();
+        assert!(matches!(action, Action::Sleep));
+        assert!(mac.is_busy());
+    }
+}
@@ -1,24 +1,34 @@
 use core::fmt::Debug;
 
 use embedded_hal::blocking::delay::DelayUs;
-use radio::modulation::lora::LoRaChannel;
 use radio::{Busy, Channel, Receive, Transmit};
 use rand_core::RngCore;
 
 pub use crate::device::class_a::*;
+pub use crate::device::class_b::*;
+pub use crate::device::class_c::*;
 use crate::device::error::DeviceError;
+pub use crate::device::machine::*;
 pub use crate::device::state::*;
 use crate::lorawan::{
-    DevNonce, JoinAccept, JoinRequest, Settings, JOIN_ACCEPT_DELAY, MAX_PACKET_SIZE,
+    DevNonce, Downlink, JoinAccept, JoinRequest, Settings, JOIN_ACCEPT_DELAY, MAX_PACKET_SIZE,
+};
+use crate::radio::{
+    ChannelMask, Clock, LoRaInfo, LoRaRadio, NoHooks, RadioChannel, RadioError, RadioHooks,
+    RegionalParameters,
 };
-use crate::radio::{LoRaInfo, LoRaRadio, Region};
 
+#[cfg(feature = "async")]
+mod asynch;
 mod class_a;
+mod class_b;
+mod class_c;
 pub mod error;
+mod machine;
 mod state;
 
-type JoinResult<RXTX, TIM, RNG, ERR, R> =
-    Result<Device<RXTX, TIM, RNG, ERR, DeviceState<R>>, DeviceError<RXTX, TIM, RNG, ERR>>;
+type JoinResult<RXTX, TIM, RNG, ERR, R, H> =
+    Result<Device<RXTX, TIM, RNG, ERR, DeviceState<R>, H>, DeviceError<RXTX, TIM, RNG, ERR, H>>;
 
 /// Represents a generic LoRaWAN device. The state can be either [Credentials] for
 /// devices that have not joined a network, or [DeviceState] for devices that have.
@@ -27,100 +37,256 @@ type JoinResult<RXTX, TIM, RNG, ERR, R> =
 /// [DeviceState]: crate::device::DeviceState
 #[derive(Debug)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
-pub struct Device<RXTX, TIM, RNG, ERR, STATE> {
-    radio: LoRaRadio<RXTX, TIM, RNG, ERR>,
+pub struct Device<RXTX, TIM, RNG, ERR, STATE, H = NoHooks> {
+    radio: LoRaRadio<RXTX, TIM, RNG, ERR, H>,
     state: STATE,
 }
 
-impl<RXTX, TIM, RNG, ERR, STATE> Device<RXTX, TIM, RNG, ERR, STATE> {
-    pub fn as_lora_radio(&self) -> &LoRaRadio<RXTX, TIM, RNG, ERR> {
+impl<RXTX, TIM, RNG, ERR, STATE, H> Device<RXTX, TIM, RNG, ERR, STATE, H> {
+    pub fn as_lora_radio(&self) -> &LoRaRadio<RXTX, TIM, RNG, ERR, H> {
         &self.radio
     }
 
-    pub fn as_mut_lora_radio(&mut self) -> &mut LoRaRadio<RXTX, TIM, RNG, ERR> {
+    pub fn as_mut_lora_radio(&mut self) -> &mut LoRaRadio<RXTX, TIM, RNG, ERR, H> {
         &mut self.radio
     }
+
+    pub fn as_state(&self) -> &STATE {
+        &self.state
+    }
+
+    pub fn as_mut_state(&mut self) -> &mut STATE {
+        &mut self.state
+    }
 }
 
-impl<RXTX, TIM, RNG, ERR, INFO, CH> Device<RXTX, TIM, RNG, ERR, Credentials>
-where
-    RXTX: Receive<Error = ERR, Info = INFO>,
-    RXTX: Transmit<Error = ERR>,
-    RXTX: Channel<Channel = CH, Error = ERR>,
-    RXTX: Busy<Error = ERR>,
-    TIM: DelayUs<u32>,
-    RNG: RngCore,
-    ERR: Debug,
-    INFO: Into<LoRaInfo>,
-    CH: From<LoRaChannel>,
-{
+impl<RXTX, TIM, RNG, ERR, H> Device<RXTX, TIM, RNG, ERR, Credentials, H> {
     /// Creates a new LoRaWAN device through Over-The-Air-Activation. It must join a network with
     /// [join] before it can be used. Alternatively, an ABP-joined device can be constructed with
     /// [new_abp].
-    pub fn new_otaa(radio: LoRaRadio<RXTX, TIM, RNG, ERR>, credentials: Credentials) -> Self {
+    pub fn new_otaa(radio: LoRaRadio<RXTX, TIM, RNG, ERR, H>, credentials: Credentials) -> Self {
         Device {
             radio,
             state: credentials,
         }
     }
 
-    /// Attempts to join this device to a network.
-    pub fn join<R: Region>(mut self) -> JoinResult<RXTX, TIM, RNG, ERR, R> {
-        let dev_nonce = DevNonce::new(self.radio.random_nonce()?);
-
-        let join_request = JoinRequest::new(&self.state, &dev_nonce);
-        let mut buf = [0; MAX_PACKET_SIZE];
-
-        match self.radio.lorawan_transmit_delayed::<R>(
-            join_request.payload(),
-            &mut buf,
-            0,
-            JOIN_ACCEPT_DELAY,
-            &Settings::default(),
-        )? {
-            None => Err(DeviceError::Join(self)),
-            Some((n, _)) => {
-                let state =
-                    JoinAccept::from_data(&mut buf[..n])?.extract_state(&self.state, &dev_nonce);
-
-                let device = Device {
-                    radio: self.radio,
-                    state,
-                };
+    /// Completes a join with the response to a join-request, if it is a valid join-accept. A
+    /// missing or invalid response, e.g. one meant for another device, is a failed attempt.
+    fn accept<R: RegionalParameters>(
+        self,
+        response: Option<&mut [u8]>,
+        dev_nonce: &DevNonce,
+        region: R,
+        channels: ChannelMask,
+    ) -> JoinResult<RXTX, TIM, RNG, ERR, R, H> {
+        let state = response.map(|buf| {
+            JoinAccept::from_data(buf)?.extract_state(&self.state, dev_nonce, region, channels)
+        });
 
+        match state {
+            Some(Ok(state)) => {
                 #[cfg(feature = "defmt")]
                 defmt::trace!("joined successfully");
 
-                Ok(device)
+                Ok(Device {
+                    radio: self.radio,
+                    state,
+                })
             }
+            _ => Err(DeviceError::Join(self)),
+        }
+    }
+
+    /// Handles a radio error during a join. Errors of the hardware end the join, but a region or
+    /// sub-band the radio can not use, or a busy channel, is a failed attempt.
+    fn join_failed<R>(self, error: RadioError<ERR>) -> JoinResult<RXTX, TIM, RNG, ERR, R, H> {
+        match error {
+            RadioError::Radio(_) | RadioError::Random(_) => Err(DeviceError::Radio(error)),
+            _ => Err(DeviceError::Join(self)),
         }
     }
 }
 
-impl<RXTX, TIM, RNG, ERR, INFO, CH, R> Device<RXTX, TIM, RNG, ERR, DeviceState<R>>
+impl<RXTX, TIM, RNG, ERR, INFO, CH, H> Device<RXTX, TIM, RNG, ERR, Credentials, H>
 where
     RXTX: Receive<Error = ERR, Info = INFO>,
     RXTX: Transmit<Error = ERR>,
     RXTX: Channel<Channel = CH, Error = ERR>,
     RXTX: Busy<Error = ERR>,
-    TIM: DelayUs<u32>,
+    TIM: DelayUs<u32> + Clock,
     RNG: RngCore,
     ERR: Debug,
     INFO: Into<LoRaInfo>,
-    CH: From<LoRaChannel>,
-    R: Region,
+    CH: RadioChannel,
+    H: RadioHooks<RXTX>,
 {
+    /// Attempts to join this device to a network in `region`.
+    pub fn join<R: RegionalParameters + Clone>(
+        self,
+        region: R,
+    ) -> JoinResult<RXTX, TIM, RNG, ERR, R, H> {
+        let join_channels = region.join_channel_mask();
+        let channels = region.default_channel_mask();
+        self.join_on(region, join_channels, channels)
+    }
+
+    /// Attempts to join this device to a network on each of the [sub-bands] of `region` in turn,
+    /// until one of them succeeds. The device keeps using the sub-band it joined on.
+    ///
+    /// [sub-bands]: crate::radio::Region::SUB_BANDS
+    pub fn join_scan<R: RegionalParameters + Clone>(
+        self,
+        region: R,
+    ) -> JoinResult<RXTX, TIM, RNG, ERR, R, H> {
+        self.join_each(join_attempts(core::slice::from_ref(&region)))
+    }
+
+    /// Attempts to join this device to a network in each of `regions` in turn, scanning their
+    /// sub-bands, until one of them succeeds. The region the device joined in is available through
+    /// [DeviceState::region].
+    ///
+    /// [DeviceState::region]: crate::device::DeviceState::region
+    pub fn join_detect<R: RegionalParameters + Clone>(
+        self,
+        regions: &[R],
+    ) -> JoinResult<RXTX, TIM, RNG, ERR, R, H> {
+        self.join_each(join_attempts(regions))
+    }
+
+    /// Attempts to join this device to a network, using only the uplink channels enabled in
+    /// `channels`, e.g. a single [sub-band]. The device keeps using these channels after joining,
+    /// unless the network configures others.
+    ///
+    /// [sub-band]: crate::radio::ChannelMask::sub_band
+    pub fn join_with_channels<R: RegionalParameters + Clone>(
+        self,
+        region: R,
+        channels: ChannelMask,
+    ) -> JoinResult<RXTX, TIM, RNG, ERR, R, H> {
+        self.join_on(region, channels.clone(), channels)
+    }
+
+    /// Makes each of `attempts` from [join_attempts] in turn, until one of them succeeds.
+    fn join_each<R: RegionalParameters + Clone>(
+        mut self,
+        attempts: impl Iterator<Item = JoinAttempt<R>>,
+    ) -> JoinResult<RXTX, TIM, RNG, ERR, R, H> {
+        for (region, join_channels, channels) in attempts {
+            match self.join_on(region, join_channels, channels) {
+                Err(DeviceError::Join(device)) => self = device,
+                result => return result,
+            }
+        }
+
+        Err(DeviceError::Join(self))
+    }
+
+    /// Sends a join-request on one of `join_channels`. The joined device uses `channels`, unless
+    /// the join-accept specifies otherwise.
+    fn join_on<R: RegionalParameters + Clone>(
+        mut self,
+        region: R,
+        join_channels: ChannelMask,
+        channels: ChannelMask,
+    ) -> JoinResult<RXTX, TIM, RNG, ERR, R, H> {
+        let dev_nonce = DevNonce::new(self.radio.random_nonce()?);
+
+        let join_request = JoinRequest::new(&self.state, &dev_nonce);
+        let mut buf = [0; MAX_PACKET_SIZE];
+
+        let mut settings = Settings::default_for(region.clone());
+        settings.set_channel_mask(join_channels);
+
+        let response = self.radio.lorawan_transmit_delayed(
+            join_request.payload(),
+            &mut buf,
+            region.join_data_rate(),
+            region.downlink_dwell_time(),
+            JOIN_ACCEPT_DELAY,
+            &settings,
+        );
+        match response {
+            Ok(response) => {
+                let response = response.map(|(n, _)| &mut buf[..n]);
+                self.accept(response, &dev_nonce, region, channels)
+            }
+            Err(error) => self.join_failed(error),
+        }
+    }
+}
+
+/// A region, the channels to send a join-request on in it, and the channels to use after joining.
+type JoinAttempt<R> = (R, ChannelMask, ChannelMask);
+
+/// Returns the join attempts that scan `regions` in order: one on each sub-band of a region with
+/// [sub-bands], or a regular join in a region without.
+///
+/// [sub-bands]: crate::radio::Region::SUB_BANDS
+fn join_attempts<R: RegionalParameters + Clone>(
+    regions: &[R],
+) -> impl Iterator<Item = JoinAttempt<R>> + '_ {
+    regions.iter().flat_map(|region| {
+        let join = region.sub_bands().is_empty().then(|| {
+            let join_channels = region.join_channel_mask();
+            (region.clone(), join_channels, region.default_channel_mask())
+        });
+        let sub_bands = region
+            .sub_bands()
+            .iter()
+            .map(move |channels| (region.clone(), channels.clone(), channels.clone()));
+        join.into_iter().chain(sub_bands)
+    })
+}
+
+impl<RXTX, TIM, RNG, ERR, R: RegionalParameters, H> Device<RXTX, TIM, RNG, ERR, DeviceState<R>, H> {
     /// Creates a joined device through Activation By Personalization. Consider using [new_otaa]
     /// instead, as it is more secure.
-    pub fn new_abp(radio: LoRaRadio<RXTX, TIM, RNG, ERR>, session: Session) -> Self {
-        let state = DeviceState::new(session, Settings::default());
+    pub fn new_abp(radio: LoRaRadio<RXTX, TIM, RNG, ERR, H>, session: Session, region: R) -> Self {
+        let state = DeviceState::new(session, Settings::default_for(region));
 
         Device { radio, state }
     }
 
     /// Configures this device to have class A behavior: listening for downlinks only after
     /// transmitting an uplink.
-    pub fn into_class_a(self) -> ClassA<RXTX, TIM, RNG, ERR, R> {
+    pub fn into_class_a(self) -> ClassA<RXTX, TIM, RNG, ERR, R, H> {
+        self.into()
+    }
+
+    /// Configures this device to have class B behavior: listening for downlinks in ping slots that
+    /// are synchronized to the beacon of the network, as well as after transmitting an uplink. The
+    /// radio must support receiving beacons through [RadioHooks::beacon_channel].
+    ///
+    /// [RadioHooks::beacon_channel]: crate::radio::RadioHooks::beacon_channel
+    pub fn into_class_b(self) -> ClassB<RXTX, TIM, RNG, ERR, R, H> {
         self.into()
     }
+
+    /// Configures this device to have class C behavior: listening for downlinks on RX2 whenever it
+    /// is not transmitting or listening on RX1. The switch is announced to the network in the next
+    /// uplink if it implements LoRaWAN 1.1.
+    pub fn into_class_c(mut self) -> ClassC<RXTX, TIM, RNG, ERR, R, H> {
+        self.state.announce_device_mode(DeviceClass::C);
+        self.into()
+    }
+
+    /// Decrypts a downlink in `rx`, and moves its payload to the start of `rx`.
+    fn decode(
+        &mut self,
+        downlink: Option<(usize, LoRaInfo)>,
+        rx: &mut [u8],
+    ) -> TransmitResult<RXTX, TIM, RNG, ERR, H> {
+        match downlink {
+            None => Ok(None),
+            Some((n, info)) => {
+                #[cfg(feature = "defmt")]
+                defmt::trace!("received downlink");
+                let downlink = Downlink::from_data(&mut rx[..n], &mut self.state)?;
+                let payload = downlink.as_bytes();
+                rx[..payload.len()].copy_from_slice(payload);
+                Ok(Some((payload.len(), info)))
+            }
+        }
+    }
 }
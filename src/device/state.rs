@@ -1,4 +1,6 @@
+use crate::lorawan::mac::{MacAnswers, TxParamSetupReq};
 use crate::lorawan::{AppEui, AppKey, AppSKey, DevAddr, DevEui, NwkSKey, Settings};
+use crate::radio::{Hz, RegionalParameters};
 
 /// Credentials needed to join a device to a network. A device that has not joined a network will
 /// use this as state.
@@ -38,23 +40,54 @@ pub struct DeviceState<R> {
     session: Session,
     settings: Settings<R>,
     tx_dr: usize,
+    adr: bool,
+    raise_data_rate: bool,
+    uplink_dwell_time: bool,
+    downlink_dwell_time: bool,
     fcnt_up: u32,
     fcnt_down: u32,
     adr_ack_cnt: u32,
+    mac_answers: MacAnswers,
+    mac_version: MacVersion,
+    pending_device_mode: Option<DeviceClass>,
+    class_b: bool,
+    ping_slot_periodicity: u8,
+    pending_ping_slot_periodicity: Option<u8>,
+    ping_slot_frequency: Option<Hz>,
+    ping_slot_dr: Option<usize>,
+    beacon_frequency: Option<Hz>,
 }
 
-impl<R> DeviceState<R> {
+impl<R: RegionalParameters> DeviceState<R> {
+    /// Creates the state of a newly joined device, which starts transmitting at the data rate used
+    /// for joining.
     pub fn new(session: Session, settings: Settings<R>) -> Self {
+        let region = settings.region();
         DeviceState {
             session,
+            tx_dr: region.join_data_rate(),
+            adr: true,
+            raise_data_rate: false,
+            uplink_dwell_time: region.uplink_dwell_time(),
+            downlink_dwell_time: region.downlink_dwell_time(),
             settings,
-            tx_dr: 0,
             fcnt_up: 0,
             fcnt_down: 0,
             adr_ack_cnt: 0,
+            mac_answers: MacAnswers::default(),
+            mac_version: MacVersion::V1_0,
+            pending_device_mode: None,
+            class_b: false,
+            ping_slot_periodicity: 7,
+            pending_ping_slot_periodicity: None,
+            ping_slot_frequency: None,
+            ping_slot_dr: None,
+            beacon_frequency: None,
         }
     }
+}
 
+impl<R> DeviceState<R> {
     pub fn session(&self) -> &Session {
         &self.session
     }
@@ -63,10 +96,79 @@ impl<R> DeviceState<R> {
         &self.settings
     }
 
+    pub fn settings_mut(&mut self) -> &mut Settings<R> {
+        &mut self.settings
+    }
+
+    /// The region the device operates in.
+    pub fn region(&self) -> &R {
+        self.settings.region()
+    }
+
     pub fn tx_dr(&self) -> usize {
         self.tx_dr
     }
 
+    /// Sets the data rate of uplinks, e.g. to one of the LR-FHSS data rates, which are not used by
+    /// default. The network may still change it with a LinkADRReq.
+    pub fn set_tx_dr(&mut self, tx_dr: usize) {
+        self.tx_dr = tx_dr;
+    }
+
+    /// Whether the network controls the data rate with ADR, which is signalled in every uplink.
+    /// ADR is enabled by default.
+    pub fn adr(&self) -> bool {
+        self.adr
+    }
+
+    pub fn set_adr(&mut self, adr: bool) {
+        self.adr = adr;
+    }
+
+    /// Whether uplinks that are too large for the current data rate may be sent at a higher data
+    /// rate instead of being rejected. This only applies while [adr] is off, as the network
+    /// controls the data rate otherwise.
+    ///
+    /// [adr]: DeviceState::adr
+    pub fn raise_data_rate(&self) -> bool {
+        self.raise_data_rate
+    }
+
+    pub fn set_raise_data_rate(&mut self, raise_data_rate: bool) {
+        self.raise_data_rate = raise_data_rate;
+    }
+
+    /// Whether uplinks are limited to a dwell time of 400 ms.
+    pub fn uplink_dwell_time(&self) -> bool {
+        self.uplink_dwell_time
+    }
+
+    /// Whether downlinks are limited to a dwell time of 400 ms.
+    pub fn downlink_dwell_time(&self) -> bool {
+        self.downlink_dwell_time
+    }
+
+    /// The maximum EIRP in dBm the device may transmit with.
+    pub fn max_eirp(&self) -> i8 {
+        self.settings.max_eirp()
+    }
+
+    /// Applies the transmit parameters requested by the network.
+    pub fn set_tx_params(&mut self, req: &TxParamSetupReq) {
+        self.uplink_dwell_time = req.uplink_dwell_time();
+        self.downlink_dwell_time = req.downlink_dwell_time();
+        self.settings.set_max_eirp(req.max_eirp());
+    }
+
+    /// MAC commands that will be sent along with the next uplink.
+    pub fn mac_answers(&self) -> &MacAnswers {
+        &self.mac_answers
+    }
+
+    pub fn mac_answers_mut(&mut self) -> &mut MacAnswers {
+        &mut self.mac_answers
+    }
+
     pub fn fcnt_up(&self) -> u32 {
         self.fcnt_up
     }
@@ -82,6 +184,116 @@ impl<R> DeviceState<R> {
     pub fn increment_fcnt_down(&mut self) {
         self.fcnt_down += 1;
     }
+
+    /// The version of LoRaWAN the network implements.
+    pub fn mac_version(&self) -> MacVersion {
+        self.mac_version
+    }
+
+    pub fn set_mac_version(&mut self, mac_version: MacVersion) {
+        self.mac_version = mac_version;
+    }
+
+    /// Announces a switch to `class` with a DeviceModeInd in every uplink until the network
+    /// confirms it, if the network implements LoRaWAN 1.1. Older networks must be configured to
+    /// use the class out of band.
+    pub fn announce_device_mode(&mut self, class: DeviceClass) {
+        if self.mac_version == MacVersion::V1_1 {
+            self.pending_device_mode = Some(class);
+        }
+    }
+
+    /// The class to announce to the network with a DeviceModeInd, until it answers.
+    pub fn pending_device_mode(&self) -> Option<DeviceClass> {
+        self.pending_device_mode
+    }
+
+    /// Processes a DeviceModeConf for `class`, which stops the announcement if it confirms the
+    /// class the device switched to last.
+    pub fn confirm_device_mode(&mut self, class: u8) {
+        if self
+            .pending_device_mode
+            .is_some_and(|pending| pending as u8 == class)
+        {
+            self.pending_device_mode = None;
+        }
+    }
+
+    /// Whether the device is synchronized to the beacon and opens ping slots, which is signalled to
+    /// the network in every uplink.
+    pub fn class_b(&self) -> bool {
+        self.class_b
+    }
+
+    pub fn set_class_b(&mut self, class_b: bool) {
+        self.class_b = class_b;
+    }
+
+    /// The periodicity of the ping slots the network knows about. The device opens a ping slot
+    /// every 2^`periodicity` seconds.
+    pub fn ping_slot_periodicity(&self) -> u8 {
+        self.ping_slot_periodicity
+    }
+
+    /// The periodicity to announce to the network with a PingSlotInfoReq, until it answers.
+    pub fn pending_ping_slot_periodicity(&self) -> Option<u8> {
+        self.pending_ping_slot_periodicity
+    }
+
+    /// Requests the network to use a periodicity from 0 to 7. The device keeps using the old
+    /// periodicity until the network answers.
+    pub fn request_ping_slot_periodicity(&mut self, periodicity: u8) {
+        self.pending_ping_slot_periodicity = Some(periodicity.min(7));
+    }
+
+    /// Processes a PingSlotInfoAns, which makes the requested periodicity take effect.
+    pub fn confirm_ping_slot_periodicity(&mut self) {
+        if let Some(periodicity) = self.pending_ping_slot_periodicity.take() {
+            self.ping_slot_periodicity = periodicity;
+        }
+    }
+
+    /// The frequency of the ping slots, or `None` if they use the default frequencies of the
+    /// region.
+    pub fn ping_slot_frequency(&self) -> Option<Hz> {
+        self.ping_slot_frequency
+    }
+
+    /// The data rate of the ping slots, or `None` if they use the data rate of the beacon.
+    pub fn ping_slot_dr(&self) -> Option<usize> {
+        self.ping_slot_dr
+    }
+
+    /// Applies the ping slot channel requested by the network.
+    pub fn set_ping_slot_channel(&mut self, frequency: Option<Hz>, dr: usize) {
+        self.ping_slot_frequency = frequency;
+        self.ping_slot_dr = Some(dr);
+    }
+
+    /// The frequency of the beacon, or `None` if it uses the default frequencies of the region.
+    pub fn beacon_frequency(&self) -> Option<Hz> {
+        self.beacon_frequency
+    }
+
+    pub fn set_beacon_frequency(&mut self, frequency: Option<Hz>) {
+        self.beacon_frequency = frequency;
+    }
+}
+
+/// A version of the LoRaWAN specification.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum MacVersion {
+    V1_0,
+    V1_1,
+}
+
+/// The classes a device can switch between. Its value is the class in a DeviceModeInd.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DeviceClass {
+    A = 0x00,
+    C = 0x02,
 }
 
 /// Session data for a device joined to a network.
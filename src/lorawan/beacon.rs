@@ -0,0 +1,212 @@
+use core::time::Duration;
+
+use lorawan_encoding::default_crypto::DefaultFactory;
+use lorawan_encoding::keys::{CryptoFactory, Encrypter, AES128};
+
+use crate::lorawan::{DevAddr, PacketError};
+use crate::radio::BeaconParameters;
+
+/// The time between two beacons.
+pub const BEACON_PERIOD: Duration = Duration::from_secs(128);
+
+/// The time at the start of each beacon period that is reserved for the beacon.
+pub const BEACON_RESERVED: Duration = Duration::from_millis(2120);
+
+/// How long a device keeps opening ping slots without receiving beacons, before it falls back to
+/// class A.
+pub const BEACONLESS_PERIOD: Duration = Duration::from_secs(7200);
+
+/// The length of a ping slot.
+pub const PING_SLOT: Duration = Duration::from_millis(30);
+
+/// The number of ping slots in a beacon period, between the reserved time and the guard time.
+pub const PING_SLOTS: u32 = 4096;
+
+/// A beacon, which gateways broadcast at the start of every beacon period to synchronize class B
+/// devices.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Beacon {
+    time: u32,
+    gateway: Option<GatewayInfo>,
+}
+
+impl Beacon {
+    /// Parses a beacon in the format of a region. If only the CRC of the gateway-specific field
+    /// does not match, the beacon is still valid, but its gateway-specific field is dropped.
+    pub fn from_data(data: &[u8], params: &BeaconParameters<'_>) -> Result<Self, PacketError> {
+        if data.len() != params.size() {
+            return Err(PacketError::InvalidBeacon);
+        }
+
+        let time_start = params.rfu1_size();
+        let (common, gateway) = data.split_at(time_start + 4 + 2);
+        let time = u32::from_le_bytes([
+            common[time_start],
+            common[time_start + 1],
+            common[time_start + 2],
+            common[time_start + 3],
+        ]);
+        if !crc_matches(common) {
+            return Err(PacketError::InvalidBeacon);
+        }
+
+        let gateway = crc_matches(gateway).then(|| {
+            let mut info = [0; 6];
+            info.copy_from_slice(&gateway[1..7]);
+            GatewayInfo {
+                info_desc: gateway[0],
+                info,
+            }
+        });
+
+        Ok(Beacon { time, gateway })
+    }
+
+    /// The time at the start of the beacon period, in seconds since the GPS epoch (6 January 1980)
+    /// modulo 2^32. It is always a multiple of the beacon period.
+    pub fn time(&self) -> u32 {
+        self.time
+    }
+
+    /// The gateway-specific field, or `None` if it was corrupted.
+    pub fn gateway(&self) -> Option<&GatewayInfo> {
+        self.gateway.as_ref()
+    }
+}
+
+/// The gateway-specific field of a beacon.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GatewayInfo {
+    info_desc: u8,
+    info: [u8; 6],
+}
+
+impl GatewayInfo {
+    /// Describes the contents of [info]. Values 0, 1 and 2 indicate the coordinates of the first,
+    /// second and third antenna of the gateway.
+    ///
+    /// [info]: GatewayInfo::info
+    pub fn info_desc(&self) -> u8 {
+        self.info_desc
+    }
+
+    pub fn info(&self) -> &[u8; 6] {
+        &self.info
+    }
+
+    /// The latitude and longitude of the gateway antenna in degrees, if the beacon contains them.
+    pub fn coordinates(&self) -> Option<(f32, f32)> {
+        if self.info_desc > 2 {
+            return None;
+        }
+
+        // Both are 24-bit two's complement numbers, scaled to the range of the coordinate
+        let value = |bytes: &[u8]| i32::from_le_bytes([0, bytes[0], bytes[1], bytes[2]]) >> 8;
+        let latitude = value(&self.info[0..3]) as f32 * 90.0 / (1 << 23) as f32;
+        let longitude = value(&self.info[3..6]) as f32 * 180.0 / (1 << 23) as f32;
+
+        Some((latitude, longitude))
+    }
+}
+
+/// Returns the first ping slot of device `dev_addr` in the beacon period starting at GPS time
+/// `time`, if it opens a ping slot every `ping_period` slots. The slot is randomized every period
+/// so that devices do not keep colliding.
+pub fn ping_offset(time: u32, dev_addr: &DevAddr, ping_period: u32) -> u32 {
+    let mut block = [0; 16];
+    block[0..4].copy_from_slice(&time.to_le_bytes());
+    block[4..8].copy_from_slice(dev_addr.as_bytes());
+
+    DefaultFactory
+        .new_enc(&AES128([0; 16]))
+        .encrypt_block((&mut block).into());
+
+    u16::from_le_bytes([block[0], block[1]]) as u32 % ping_period
+}
+
+/// Checks the CRC-16 (CCITT, with an initial value of zero) at the end of a part of a beacon.
+fn crc_matches(data: &[u8]) -> bool {
+    let (data, crc) = data.split_at(data.len() - 2);
+    let mut expected = 0u16;
+    for &byte in data {
+        expected ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            expected = if expected & 0x8000 != 0 {
+                (expected << 1) ^ 0x1021
+            } else {
+                expected << 1
+            };
+        }
+    }
+
+    expected == u16::from_le_bytes([crc[0], crc[1]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::radio::{RegionalParameters, EU868};
+
+    /// The example beacon of the EU868 region, at GPS time 0xCC020000 and with the coordinates of
+    /// the first antenna of the gateway.
+    const EU868_BEACON: [u8; 17] = [
+        0x00, 0x00, 0x00, 0x00, 0x02, 0xCC, 0xA2, 0x7E, 0x00, 0x01, 0x20, 0x00, 0x00, 0x81, 0x03,
+        0xDE, 0x55,
+    ];
+
+    #[test]
+    fn crc_matches_example() {
+        assert!(crc_matches(&EU868_BEACON[..8]));
+        assert!(crc_matches(&EU868_BEACON[8..]));
+        assert!(!crc_matches(&[
+            0x00, 0x00, 0x00, 0x01, 0x02, 0xCC, 0xA2, 0x7E
+        ]));
+    }
+
+    #[test]
+    fn parses_beacon() {
+        let beacon = Beacon::from_data(&EU868_BEACON, EU868.beacon()).unwrap();
+        assert_eq!(beacon.time(), 0xCC020000);
+
+        let gateway = beacon.gateway().unwrap();
+        assert_eq!(gateway.info_desc(), 0);
+        assert_eq!(gateway.info(), &[0x01, 0x20, 0x00, 0x00, 0x81, 0x03]);
+        let (latitude, longitude) = gateway.coordinates().unwrap();
+        assert!((latitude - 0.0879).abs() < 0.0001);
+        assert!((longitude - 4.9274).abs() < 0.0001);
+    }
+
+    #[test]
+    fn drops_corrupted_gateway_info() {
+        let mut data = EU868_BEACON;
+        data[10] ^= 1;
+        let beacon = Beacon::from_data(&data, EU868.beacon()).unwrap();
+        assert_eq!(beacon.time(), 0xCC020000);
+        assert_eq!(beacon.gateway(), None);
+    }
+
+    #[test]
+    fn rejects_corrupted_beacon() {
+        let mut data = EU868_BEACON;
+        data[4] ^= 1;
+        assert!(matches!(
+            Beacon::from_data(&data, EU868.beacon()),
+            Err(PacketError::InvalidBeacon)
+        ));
+        assert!(matches!(
+            Beacon::from_data(&EU868_BEACON[..16], EU868.beacon()),
+            Err(PacketError::InvalidBeacon)
+        ));
+    }
+
+    #[test]
+    fn ping_offset_is_randomized() {
+        let dev_addr = DevAddr::from_bytes([0xDA, 0x1B, 0x01, 0x26]);
+        // The first two bytes of the encrypted blocks are 0xE22C and 0xD6DC
+        assert_eq!(ping_offset(0xCC020000, &dev_addr, 32), 12);
+        assert_eq!(ping_offset(0xCC020000, &dev_addr, 4096), 556);
+        assert_eq!(ping_offset(0, &dev_addr, 128), 92);
+    }
+}
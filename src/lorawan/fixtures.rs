@@ -0,0 +1,8 @@
+/// The AppKey of the device in the test vectors.
+pub const APP_KEY: u128 = 0x2B7E151628AED2A6ABF7158809CF4F3C;
+
+/// A join-accept for device address 0x26011BDA, encrypted with [APP_KEY].
+pub const JOIN_ACCEPT: [u8; 17] = [
+    0x20, 0x35, 0x13, 0xEF, 0x54, 0xC0, 0xCE, 0x6B, 0x65, 0x04, 0x51, 0xDB, 0x4F, 0x49, 0xF6, 0x1D,
+    0xB7,
+];
@@ -0,0 +1,395 @@
+use lorawan_encoding::maccommands::SerializableMacCommand;
+
+use crate::radio::Hz;
+
+pub const LINK_CHECK: u8 = 0x02;
+pub const LINK_ADR: u8 = 0x03;
+pub const DUTY_CYCLE: u8 = 0x04;
+pub const RX_PARAM_SETUP: u8 = 0x05;
+pub const DEV_STATUS: u8 = 0x06;
+pub const NEW_CHANNEL: u8 = 0x07;
+pub const RX_TIMING_SETUP: u8 = 0x08;
+pub const TX_PARAM_SETUP: u8 = 0x09;
+pub const DL_CHANNEL: u8 = 0x0A;
+pub const DEVICE_TIME: u8 = 0x0D;
+pub const PING_SLOT_INFO: u8 = 0x10;
+pub const PING_SLOT_CHANNEL: u8 = 0x11;
+pub const BEACON_FREQ: u8 = 0x13;
+pub const DEVICE_MODE: u8 = 0x20;
+
+/// The maximum EIRP in dBm for each MaxEIRP index of a TxParamSetupReq.
+const MAX_EIRP: [i8; 16] = [
+    8, 10, 12, 13, 14, 16, 18, 20, 21, 24, 26, 27, 29, 30, 33, 36,
+];
+
+/// Returns the payload size of a MAC command sent by the network, or `None` if it is unknown.
+fn downlink_payload_size(cid: u8) -> Option<usize> {
+    match cid {
+        LINK_CHECK => Some(2),
+        LINK_ADR => Some(4),
+        DUTY_CYCLE => Some(1),
+        RX_PARAM_SETUP => Some(4),
+        DEV_STATUS => Some(0),
+        NEW_CHANNEL => Some(5),
+        RX_TIMING_SETUP => Some(1),
+        TX_PARAM_SETUP => Some(1),
+        DL_CHANNEL => Some(4),
+        DEVICE_TIME => Some(5),
+        PING_SLOT_INFO => Some(0),
+        PING_SLOT_CHANNEL => Some(4),
+        BEACON_FREQ => Some(3),
+        DEVICE_MODE => Some(1),
+        _ => None,
+    }
+}
+
+/// A MAC command sent by the network, consisting of a command identifier and its payload.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MacCommand<'a> {
+    cid: u8,
+    payload: &'a [u8],
+}
+
+impl<'a> MacCommand<'a> {
+    pub fn cid(&self) -> u8 {
+        self.cid
+    }
+
+    pub fn payload(&self) -> &'a [u8] {
+        self.payload
+    }
+}
+
+/// Iterates over the MAC commands in FOpts or in an FRMPayload on port 0. Iteration stops at the
+/// first unknown or truncated command, as the size of the commands after it cannot be known.
+pub struct MacCommands<'a>(&'a [u8]);
+
+impl<'a> MacCommands<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        MacCommands(data)
+    }
+}
+
+impl<'a> Iterator for MacCommands<'a> {
+    type Item = MacCommand<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (&cid, rest) = self.0.split_first()?;
+        let size = downlink_payload_size(cid).filter(|&size| size <= rest.len());
+        match size {
+            None => {
+                self.0 = &[];
+                None
+            }
+            Some(size) => {
+                let (payload, rest) = rest.split_at(size);
+                self.0 = rest;
+                Some(MacCommand { cid, payload })
+            }
+        }
+    }
+}
+
+/// Returns `true` if `data` consists only of known MAC commands that are complete, so that
+/// [MacCommands] iterates over all of it.
+pub fn is_complete(data: &[u8]) -> bool {
+    let mut rest = data;
+    while let Some((&cid, tail)) = rest.split_first() {
+        match downlink_payload_size(cid) {
+            Some(size) if size <= tail.len() => rest = &tail[size..],
+            _ => return false,
+        }
+    }
+    true
+}
+
+/// The payload of a TxParamSetupReq.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TxParamSetupReq(u8);
+
+impl TxParamSetupReq {
+    pub fn new(payload: &[u8]) -> Self {
+        TxParamSetupReq(payload[0])
+    }
+
+    pub fn downlink_dwell_time(&self) -> bool {
+        self.0 & 0b0010_0000 != 0
+    }
+
+    pub fn uplink_dwell_time(&self) -> bool {
+        self.0 & 0b0001_0000 != 0
+    }
+
+    /// The maximum EIRP in dBm.
+    pub fn max_eirp(&self) -> i8 {
+        MAX_EIRP[(self.0 & 0x0F) as usize]
+    }
+}
+
+/// The payload of a NewChannelReq.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct NewChannelReq<'a>(&'a [u8]);
+
+impl<'a> NewChannelReq<'a> {
+    pub fn new(payload: &'a [u8]) -> Self {
+        NewChannelReq(payload)
+    }
+
+    pub fn channel_index(&self) -> usize {
+        self.0[0] as usize
+    }
+
+    /// The frequency of the channel, or `None` to remove it.
+    pub fn frequency(&self) -> Option<Hz> {
+        frequency(&self.0[1..4])
+    }
+
+    pub fn min_data_rate(&self) -> usize {
+        (self.0[4] & 0x0F) as usize
+    }
+
+    pub fn max_data_rate(&self) -> usize {
+        (self.0[4] >> 4) as usize
+    }
+}
+
+/// The payload of a PingSlotChannelReq.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PingSlotChannelReq<'a>(&'a [u8]);
+
+impl<'a> PingSlotChannelReq<'a> {
+    pub fn new(payload: &'a [u8]) -> Self {
+        PingSlotChannelReq(payload)
+    }
+
+    /// The frequency of the ping slots, or `None` to use the default frequencies of the region.
+    pub fn frequency(&self) -> Option<Hz> {
+        frequency(&self.0[0..3])
+    }
+
+    pub fn data_rate(&self) -> usize {
+        (self.0[3] & 0x0F) as usize
+    }
+}
+
+/// The payload of a BeaconFreqReq.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BeaconFreqReq<'a>(&'a [u8]);
+
+impl<'a> BeaconFreqReq<'a> {
+    pub fn new(payload: &'a [u8]) -> Self {
+        BeaconFreqReq(payload)
+    }
+
+    /// The frequency of the beacon, or `None` to use the default frequencies of the region.
+    pub fn frequency(&self) -> Option<Hz> {
+        frequency(self.0)
+    }
+}
+
+/// Decodes a frequency in units of 100 Hz, where zero means that none is given.
+pub(crate) fn frequency(bytes: &[u8]) -> Option<Hz> {
+    match u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0]) {
+        0 => None,
+        frequency => Some(frequency * 100),
+    }
+}
+
+/// MAC commands waiting to be sent to the network in the FOpts of the next uplink.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MacAnswers {
+    buf: [u8; 15],
+    len: usize,
+}
+
+impl MacAnswers {
+    /// Queues a MAC command. Returns `false` if there is no room left for it.
+    #[must_use]
+    pub fn push(&mut self, cid: u8, payload: &[u8]) -> bool {
+        let end = self.len + 1 + payload.len();
+        if end > self.buf.len() {
+            return false;
+        }
+
+        self.buf[self.len] = cid;
+        self.buf[self.len + 1..end].copy_from_slice(payload);
+        self.len = end;
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+}
+
+/// Serializes all queued commands at once. This works because lorawan-encoding writes FOpts as
+/// the identifier of each command followed by its payload.
+impl SerializableMacCommand for MacAnswers {
+    fn payload_bytes(&self) -> &[u8] {
+        &self.buf[1..self.len]
+    }
+
+    fn cid(&self) -> u8 {
+        self.buf[0]
+    }
+
+    fn payload_len(&self) -> usize {
+        self.len - 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn iterates_over_commands() {
+        let data = [
+            LINK_ADR,
+            0x51,
+            0xFF,
+            0x00,
+            0x01,
+            DEV_STATUS,
+            TX_PARAM_SETUP,
+            0x3F,
+            PING_SLOT_INFO,
+            BEACON_FREQ,
+            0x18,
+            0x4F,
+            0x84,
+        ];
+        let commands: &[(u8, &[u8])] = &[
+            (LINK_ADR, &[0x51, 0xFF, 0x00, 0x01]),
+            (DEV_STATUS, &[]),
+            (TX_PARAM_SETUP, &[0x3F]),
+            (PING_SLOT_INFO, &[]),
+            (BEACON_FREQ, &[0x18, 0x4F, 0x84]),
+        ];
+        let expected = commands
+            .iter()
+            .map(|&(cid, payload)| MacCommand { cid, payload });
+        assert!(MacCommands::new(&data).eq(expected));
+    }
+
+    #[test]
+    fn stops_at_unknown_or_truncated_command() {
+        let cases: &[(&[u8], usize)] = &[
+            (&[], 0),
+            (&[0x01, DEV_STATUS], 0),
+            (&[DEV_STATUS, 0xFF, DEV_STATUS], 1),
+            (&[DUTY_CYCLE, 0x00, LINK_ADR, 0x51, 0xFF, 0x00], 1),
+            (&[NEW_CHANNEL, 0x00, 0x01], 0),
+        ];
+        for &(data, count) in cases {
+            let mut commands = MacCommands::new(data);
+            assert_eq!(commands.by_ref().count(), count, "{:02X?}", data);
+            assert_eq!(commands.next(), None, "{:02X?}", data);
+        }
+    }
+
+    #[test]
+    fn checks_that_commands_are_complete() {
+        let cases: &[(&[u8], bool)] = &[
+            (&[], true),
+            (&[DEV_STATUS, DUTY_CYCLE, 0x00], true),
+            (&[DEV_STATUS, 0x01], false),
+            (&[DEV_STATUS, DUTY_CYCLE], false),
+            (&[TX_PARAM_SETUP, 0x00, 0xFF], false),
+        ];
+        for &(data, complete) in cases {
+            assert_eq!(is_complete(data), complete, "{:02X?}", data);
+        }
+    }
+
+    #[test]
+    fn parses_tx_param_setup_req() {
+        let cases = [
+            (0x00, false, false, 8),
+            (0x01, false, false, 10),
+            (0x0A, false, false, 26),
+            (0x15, false, true, 16),
+            (0x2D, true, false, 30),
+            (0x3F, true, true, 36),
+            (0xCE, false, false, 33),
+        ];
+        for (byte, downlink, uplink, max_eirp) in cases {
+            let req = TxParamSetupReq::new(&[byte]);
+            assert_eq!(req.downlink_dwell_time(), downlink, "{:#04X}", byte);
+            assert_eq!(req.uplink_dwell_time(), uplink, "{:#04X}", byte);
+            assert_eq!(req.max_eirp(), max_eirp, "{:#04X}", byte);
+        }
+    }
+
+    #[test]
+    fn parses_new_channel_req() {
+        // Channel 3 at 867.1 MHz for DR0 to DR7
+        let req = NewChannelReq::new(&[0x03, 0x18, 0x4F, 0x84, 0x70]);
+        assert_eq!(req.channel_index(), 3);
+        assert_eq!(req.frequency(), Some(867_100_000));
+        assert_eq!((req.min_data_rate(), req.max_data_rate()), (0, 7));
+
+        let req = NewChannelReq::new(&[0x0F, 0x00, 0x00, 0x00, 0x00]);
+        assert_eq!(req.channel_index(), 15);
+        assert_eq!(req.frequency(), None);
+    }
+
+    #[test]
+    fn queues_answers_until_full() {
+        let mut answers = MacAnswers::default();
+        assert!(answers.is_empty());
+        assert!(answers.push(LINK_ADR, &[0x07]));
+        assert!(answers.push(DEV_STATUS, &[0xFF, 0x1F]));
+        assert_eq!(
+            (answers.cid(), answers.payload_bytes()),
+            (LINK_ADR, &[0x07, DEV_STATUS, 0xFF, 0x1F][..])
+        );
+        assert_eq!(answers.payload_len(), 4);
+
+        for _ in 0..10 {
+            assert!(answers.push(DUTY_CYCLE, &[]));
+        }
+        assert_eq!(answers.len(), 15);
+        assert!(!answers.push(LINK_CHECK, &[]));
+
+        answers.clear();
+        assert!(answers.is_empty());
+    }
+
+    #[test]
+    fn parses_frequencies() {
+        let cases: &[([u8; 3], Option<Hz>)] = &[
+            ([0x00, 0x00, 0x00], None),
+            ([0xD2, 0xAD, 0x84], Some(869_525_000)),
+            ([0x50, 0xE6, 0x8C], Some(923_400_000)),
+            ([0x01, 0x00, 0x00], Some(100)),
+        ];
+        for &(bytes, frequency) in cases {
+            assert_eq!(
+                BeaconFreqReq::new(&bytes).frequency(),
+                frequency,
+                "{:02X?}",
+                bytes
+            );
+
+            let payload = [bytes[0], bytes[1], bytes[2], 0xF3];
+            let req = PingSlotChannelReq::new(&payload);
+            assert_eq!(req.frequency(), frequency, "{:02X?}", bytes);
+            assert_eq!(req.data_rate(), 3);
+        }
+    }
+}
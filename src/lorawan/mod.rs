@@ -1,9 +1,14 @@
+pub use crate::lorawan::beacon::*;
 pub use crate::lorawan::constants::*;
 pub use crate::lorawan::packet::*;
 pub use crate::lorawan::settings::*;
 pub use crate::lorawan::types::*;
 
+mod beacon;
 mod constants;
+#[cfg(test)]
+pub(crate) mod fixtures;
+pub mod mac;
 mod packet;
 mod settings;
 mod types;
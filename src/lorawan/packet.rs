@@ -1,25 +1,72 @@
 use lorawan_encoding::creator::{DataPayloadCreator, JoinRequestCreator};
 use lorawan_encoding::default_crypto::DefaultFactory;
-use lorawan_encoding::maccommands::MacCommand;
+use lorawan_encoding::maccommands::SerializableMacCommand;
 use lorawan_encoding::parser;
 use lorawan_encoding::parser::{
-    DataHeader, DataPayload, EncryptedJoinAcceptPayload, FCtrl, FRMPayload, MHDRAble, PhyPayload,
+    DataHeader, DataPayload, EncryptedJoinAcceptPayload, FCtrl, PhyPayload,
 };
 
 use crate::device::{Credentials, DeviceState, Session};
+use crate::lorawan::mac::{
+    self, BeaconFreqReq, MacCommands, NewChannelReq, PingSlotChannelReq, TxParamSetupReq,
+};
 use crate::lorawan::{AppSKey, DevAddr, DevNonce, NwkSKey, Settings};
-use crate::radio::Hz;
+use crate::radio::{AddedChannel, CfListType, ChannelMask, RegionalParameters};
+
+/// The largest PHY payload any region allows: the MAC header, a MAC payload of 250 bytes and the
+/// MIC.
+pub const MAX_PACKET_SIZE: usize = 255;
 
-pub const MAX_PACKET_SIZE: usize = 242;
+/// The number of bytes in the MAC payload taken up by the frame header (without FOpts) and port.
+const MAC_PAYLOAD_OVERHEAD: usize = 8;
 
-pub struct Uplink([u8; MAX_PACKET_SIZE], usize);
+pub struct Uplink([u8; MAX_PACKET_SIZE], usize, usize);
 
 impl Uplink {
-    pub fn new<R>(
+    /// Builds an uplink for the current data rate of `state`. If `payload` does not fit, ADR is off
+    /// and the device may raise its data rate, the lowest usable data rate that does fit is used
+    /// instead.
+    /// The MAC answers of `state` are sent along, and must be cleared once the uplink has been
+    /// transmitted.
+    pub fn new<R: RegionalParameters>(
         payload: &[u8],
         port: u8,
         state: &mut DeviceState<R>,
     ) -> Result<Self, PacketError> {
+        // A pending PingSlotInfoReq or DeviceModeInd is sent along until the network answers. If
+        // FOpts are full, it is sent in a later uplink instead.
+        let mut answers = state.mac_answers().clone();
+        if let Some(periodicity) = state.pending_ping_slot_periodicity() {
+            let _ = answers.push(mac::PING_SLOT_INFO, &[periodicity]);
+        }
+        if let Some(class) = state.pending_device_mode() {
+            let _ = answers.push(mac::DEVICE_MODE, &[class as u8]);
+        }
+        let f_opts_len = answers.len();
+        let dwell_time = state.uplink_dwell_time();
+        let settings = state.settings();
+        let region = settings.region();
+        // Data rates with an M of zero, or that no enabled channel supports, cannot be used at all
+        let max_size = |dr| {
+            let max_mac_payload_size = region.max_mac_payload_size(dr, dwell_time);
+            let usable = max_mac_payload_size > 0 && settings.select_channel(dr, 0).is_some();
+            usable.then(|| max_mac_payload_size.saturating_sub(MAC_PAYLOAD_OVERHEAD + f_opts_len))
+        };
+        let fits = |dr| max_size(dr).is_some_and(|size| payload.len() <= size);
+
+        let mut data_rate = state.tx_dr();
+        // With ADR, the data rate is the network's to change
+        if !fits(data_rate) && state.raise_data_rate() && !state.adr() {
+            if let Some(dr) = (data_rate..=region.max_tx_data_rate()).find(|&dr| fits(dr)) {
+                data_rate = dr;
+            }
+        }
+        match max_size(data_rate) {
+            None => return Err(PacketError::UnusableDataRate(data_rate)),
+            Some(size) if payload.len() > size => return Err(PacketError::PayloadTooLarge(size)),
+            Some(_) => {}
+        }
+
         let session = state.session();
         let nwk_skey = (*session.nwk_skey().as_bytes()).into();
         let app_skey = (*session.app_skey().as_bytes()).into();
@@ -29,27 +76,50 @@ impl Uplink {
         phy.set_dev_addr(session.dev_addr().as_bytes());
         phy.set_f_port(port);
         phy.set_fcnt(state.fcnt_up());
-        phy.set_fctrl(&FCtrl::new(0b10000000, true));
+        // The class B bit tells the network to use the ping slots
+        let f_ctrl = ((state.adr() as u8) << 7) | ((state.class_b() as u8) << 4);
+        phy.set_fctrl(&FCtrl::new(f_ctrl, true));
         phy.set_uplink(true);
-        let payload = phy.build(payload, &[], &nwk_skey, &app_skey)?;
+        let answers: &[&dyn SerializableMacCommand] =
+            if answers.is_empty() { &[] } else { &[&answers] };
+        let payload = phy.build(payload, answers, &nwk_skey, &app_skey)?;
 
         let mut buf = [0; MAX_PACKET_SIZE];
         buf[0..payload.len()].copy_from_slice(payload);
 
         state.increment_fcnt_up();
 
-        Ok(Uplink(buf, payload.len()))
+        Ok(Uplink(buf, payload.len(), data_rate))
     }
 
     pub fn as_bytes(&self) -> &[u8] {
         &self.0[..self.1]
     }
+
+    /// The data rate this uplink must be transmitted at.
+    pub fn data_rate(&self) -> usize {
+        self.2
+    }
 }
 
+/// The offset of the FCtrl byte in a data frame, after the MAC header and the device address.
+const F_CTRL_OFFSET: usize = 5;
+
+/// The offset of FOpts in a data frame, after FCtrl and FCnt.
+const F_OPTS_OFFSET: usize = 8;
+
+/// The size of the MIC at the end of a frame.
+const MIC_SIZE: usize = 4;
+
 pub struct Downlink([u8; MAX_PACKET_SIZE], usize);
 
 impl Downlink {
-    pub fn from_data<R>(data: &mut [u8], state: &mut DeviceState<R>) -> Result<Self, PacketError> {
+    /// Decrypts and verifies a downlink, and applies any MAC commands in it to `state`. The
+    /// resulting downlink contains only the application payload, if any.
+    pub fn from_data<R: RegionalParameters>(
+        data: &mut [u8],
+        state: &mut DeviceState<R>,
+    ) -> Result<Self, PacketError> {
         let session = state.session();
         let nwk_skey = (*session.nwk_skey().as_bytes()).into();
         let app_skey = (*session.app_skey().as_bytes()).into();
@@ -59,51 +129,125 @@ impl Downlink {
                 .decrypt_if_mic_ok(&nwk_skey, &app_skey, state.fcnt_down())
                 .map_err(|_| PacketError::MICMismatch)?;
 
-            let mhdr = phy.mhdr();
-            let fhdr = phy.fhdr();
-            let f_port = phy.f_port();
-            let frm_payload = phy.frm_payload();
-            match f_port {
-                None => {
-                    // No FPort, hence no payload
-                    todo!()
+            // The frame counter moves on for every authentic frame, even one that is rejected
+            // below, so that it can not be replayed
+            state.increment_fcnt_down();
+
+            // lorawan-encoding does not know about all MAC commands, so they are parsed from the
+            // raw frame instead
+            let bytes = phy.as_data_bytes();
+            let f_opts_end = F_OPTS_OFFSET + (bytes[F_CTRL_OFFSET] & 0x0F) as usize;
+            let f_opts = &bytes[F_OPTS_OFFSET..f_opts_end];
+            let rest = &bytes[f_opts_end..bytes.len() - MIC_SIZE];
+
+            // The whole frame is checked before any of it is applied
+            let (commands, payload) = match rest.split_first() {
+                // No FPort, hence no payload
+                None => (f_opts, &[][..]),
+                Some((&port, payload)) => match port {
+                    0 if f_opts.is_empty() => (payload, &[][..]),
+                    0 => return Err(PacketError::InvalidMACPort),
+                    1..=223 => (f_opts, payload),
+                    // Port 224 is reserved for the test protocol, which is not supported
+                    port => return Err(PacketError::InvalidPort(port)),
+                },
+            };
+            if !mac::is_complete(commands) {
+                return Err(PacketError::InvalidDownlinkMACCommand);
+            }
+
+            Self::apply_mac_commands(commands, state);
+            let mut buf = [0; MAX_PACKET_SIZE];
+            buf[..payload.len()].copy_from_slice(payload);
+            let len = payload.len();
+
+            Ok(Downlink(buf, len))
+        } else {
+            Err(PacketError::Encoding(""))
+        }
+    }
+
+    /// Applies `commands`, which [mac::is_complete] has accepted. LinkADRReq, RXParamSetupReq and
+    /// DlChannelReq are not supported, and are refused. DutyCycleReq, DevStatusReq and
+    /// RXTimingSetupReq are not supported either, but their answers can not refuse them, so they
+    /// are left unanswered.
+    fn apply_mac_commands<R: RegionalParameters>(commands: &[u8], state: &mut DeviceState<R>) {
+        let tx_param_setup = state.region().tx_param_setup();
+        let new_channel = state.region().cf_list_type() == CfListType::Frequencies;
+        for command in MacCommands::new(commands) {
+            match command.cid() {
+                mac::LINK_CHECK => {}
+                // A refusal that does not fit is sent when the network repeats the request
+                mac::LINK_ADR => {
+                    let _ = state.mac_answers_mut().push(mac::LINK_ADR, &[0b000]);
+                }
+                mac::RX_PARAM_SETUP => {
+                    let _ = state.mac_answers_mut().push(mac::RX_PARAM_SETUP, &[0b000]);
                 }
-                Some(port) => {
-                    match port {
-                        0 => {
-                            // MAC Commands
-                            if let Ok(FRMPayload::MACCommands(macs)) = frm_payload {
-                                for mac in macs.mac_commands() {
-                                    match mac {
-                                        MacCommand::LinkCheckAns(_) => {}
-                                        MacCommand::LinkADRReq(_) => {}
-                                        MacCommand::DutyCycleReq(_) => {}
-                                        MacCommand::RXParamSetupReq(_) => {}
-                                        MacCommand::DevStatusReq(_) => {}
-                                        MacCommand::NewChannelReq(_) => {}
-                                        MacCommand::RXTimingSetupReq(_) => {}
-                                        _ => return Err(PacketError::InvalidDownlinkMACCommand),
-                                    }
-                                }
-                                todo!()
-                            } else {
-                                Err(PacketError::InvalidMACPort)
-                            }
-                        }
-                        port if (1..=223).contains(&port) => {
-                            // Application data
-                            todo!()
-                        }
-                        224 => {
-                            // Reserved
-                            todo!()
-                        }
-                        port => Err(PacketError::InvalidPort(port)),
+                mac::DL_CHANNEL => {
+                    let _ = state.mac_answers_mut().push(mac::DL_CHANNEL, &[0b00]);
+                }
+                mac::DUTY_CYCLE => {}
+                mac::DEV_STATUS => {}
+                mac::RX_TIMING_SETUP => {}
+                // A request is only applied if its answer can be sent, so that the network retries
+                // it otherwise
+                mac::NEW_CHANNEL if new_channel => {
+                    let req = NewChannelReq::new(command.payload());
+                    let region = state.region();
+                    // Only the channels after the default channels can be added or changed
+                    let index = req.channel_index();
+                    let index_ok =
+                        (region.channel_count()..AddedChannel::MAX_CHANNELS).contains(&index);
+                    let (min_dr, max_dr) = (req.min_data_rate(), req.max_data_rate());
+                    let dr_ok = index_ok && min_dr <= max_dr && max_dr <= region.max_tx_data_rate();
+                    // The frequency cannot be checked, as regions do not define a range
+                    let status = ((dr_ok as u8) << 1) | index_ok as u8;
+                    let queued = state.mac_answers_mut().push(mac::NEW_CHANNEL, &[status]);
+                    if queued && status == 0b11 {
+                        let channel = req
+                            .frequency()
+                            .map(|frequency| AddedChannel::new(frequency, min_dr, max_dr));
+                        state.settings_mut().set_added_channel(index, channel);
+                    }
+                }
+                // Regions with fixed channels do not support it
+                mac::NEW_CHANNEL => {}
+                mac::TX_PARAM_SETUP if tx_param_setup => {
+                    if state.mac_answers_mut().push(mac::TX_PARAM_SETUP, &[]) {
+                        state.set_tx_params(&TxParamSetupReq::new(command.payload()));
+                    }
+                }
+                // Devices in other regions must ignore it
+                mac::TX_PARAM_SETUP => {}
+                mac::DEVICE_TIME => {}
+                mac::PING_SLOT_INFO => state.confirm_ping_slot_periodicity(),
+                mac::PING_SLOT_CHANNEL => {
+                    let req = PingSlotChannelReq::new(command.payload());
+                    let dr_ok = state
+                        .region()
+                        .data_rate(req.data_rate())
+                        .is_some_and(|dr| !dr.is_uplink_only());
+                    // The frequency cannot be checked, as regions do not define a range
+                    let status = if dr_ok { 0b11 } else { 0b01 };
+                    let queued = state
+                        .mac_answers_mut()
+                        .push(mac::PING_SLOT_CHANNEL, &[status]);
+                    if queued && dr_ok {
+                        state.set_ping_slot_channel(req.frequency(), req.data_rate());
+                    }
+                }
+                mac::BEACON_FREQ => {
+                    if state.mac_answers_mut().push(mac::BEACON_FREQ, &[0b1]) {
+                        let req = BeaconFreqReq::new(command.payload());
+                        state.set_beacon_frequency(req.frequency());
                     }
                 }
+                // The DeviceModeConf only confirms the class the device already switched to
+                mac::DEVICE_MODE => state.confirm_device_mode(command.payload()[0]),
+                // Every command the iterator can size is handled above
+                _ => {}
             }
-        } else {
-            Err(PacketError::Encoding(""))
         }
     }
 
@@ -144,15 +288,23 @@ impl<'a> JoinAccept<'a> {
         Ok(JoinAccept(payload))
     }
 
-    pub fn extract_state<R>(
+    /// Decrypts and verifies the join-accept, and extracts the session and settings from it.
+    /// `channel_mask` contains the channels used to join, which the device keeps using unless the
+    /// join-accept specifies otherwise.
+    pub fn extract_state<R: RegionalParameters>(
         self,
         credentials: &Credentials,
         dev_nonce: &DevNonce,
-    ) -> DeviceState<R> {
+        region: R,
+        channel_mask: ChannelMask,
+    ) -> Result<DeviceState<R>, PacketError> {
         let app_key = (*credentials.app_key().as_bytes()).into();
         let dev_nonce = dev_nonce.as_bytes().into();
 
         let payload = self.0.decrypt(&app_key);
+        if !payload.validate_mic(&app_key) {
+            return Err(PacketError::MICMismatch);
+        }
 
         let mut bytes = [0; 4];
         bytes.copy_from_slice(payload.dev_addr().as_ref());
@@ -165,20 +317,42 @@ impl<'a> JoinAccept<'a> {
         let dl_settings = payload.dl_settings();
         let rx_delay = payload.rx_delay();
         let cf_list = payload.c_f_list().map(|frequencies| {
-            frequencies.map(|frequency| {
-                let mut buf = [0; 4];
-                buf[1..].copy_from_slice(frequency.as_ref());
-                Hz::from_be_bytes(buf)
-            })
+            let mut buf = [0; 15];
+            for (chunk, frequency) in buf.chunks_mut(3).zip(frequencies.iter()) {
+                chunk.copy_from_slice(frequency.as_ref());
+            }
+            buf
         });
 
-        let settings = Settings::new(
+        let cf_list_type = region.cf_list_type();
+        // Channels from the CFList support the same data rates as the default channels
+        let max_dr = (0..=region.max_tx_data_rate())
+            .rev()
+            .find(|&dr| region.supports_data_rate(0, dr))
+            .unwrap_or(0);
+        let first_added = region.channel_count();
+        let mut settings = Settings::new(
+            region,
             rx_delay,
             dl_settings.rx1_dr_offset(),
             dl_settings.rx2_data_rate(),
         );
+        match (cf_list_type, cf_list) {
+            (CfListType::ChannelMask, Some(cf_list)) => {
+                settings.set_channel_mask(ChannelMask::from_cf_list(&cf_list))
+            }
+            (CfListType::Frequencies, Some(cf_list)) => {
+                settings.set_channel_mask(channel_mask);
+                for (i, bytes) in cf_list.chunks(3).enumerate() {
+                    let channel = mac::frequency(bytes)
+                        .map(|frequency| AddedChannel::new(frequency, 0, max_dr));
+                    settings.set_added_channel(first_added + i, channel);
+                }
+            }
+            _ => settings.set_channel_mask(channel_mask),
+        }
 
-        DeviceState::new(session, settings)
+        Ok(DeviceState::new(session, settings))
     }
 }
 
@@ -187,8 +361,16 @@ impl<'a> JoinAccept<'a> {
 pub enum PacketError {
     InvalidDownlinkMACCommand,
     MICMismatch,
+    /// A class B beacon has the wrong size, or its time does not match its CRC.
+    InvalidBeacon,
     InvalidPort(u8),
     InvalidMACPort,
+    /// The payload does not fit in a single uplink at the data rate in use. Contains the maximum
+    /// payload size in bytes.
+    PayloadTooLarge(usize),
+    /// The data rate in use cannot carry uplinks, as the region does not allow any payload on it
+    /// or none of the enabled channels supports it. Contains the data rate.
+    UnusableDataRate(usize),
     Encoding(&'static str),
 }
 
@@ -197,3 +379,317 @@ impl From<&'static str> for PacketError {
         PacketError::Encoding(error)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device::{DeviceClass, MacVersion};
+    use crate::lorawan::fixtures::{APP_KEY, JOIN_ACCEPT};
+    use crate::lorawan::mac::MacAnswers;
+    use crate::lorawan::{AppEui, AppKey, DevEui};
+    use crate::radio::{
+        ChannelConfig, ChannelMask, Region, AS923_1, AU915, CN470_20A, EU868, US915,
+    };
+
+    fn credentials(app_key: u128) -> Credentials {
+        Credentials::new(AppEui::new(0), DevEui::new(0), AppKey::new(app_key))
+    }
+
+    fn extract_state(data: &mut [u8], app_key: u128) -> Result<DeviceState<EU868>, PacketError> {
+        JoinAccept::from_data(data)?.extract_state(
+            &credentials(app_key),
+            &DevNonce::new(1),
+            EU868,
+            <EU868 as Region>::default_channel_mask(),
+        )
+    }
+
+    #[test]
+    fn accepts_join() {
+        let mut data = JOIN_ACCEPT;
+        let state = extract_state(&mut data, APP_KEY).unwrap();
+        assert_eq!(
+            state.session().dev_addr().as_bytes(),
+            &[0xDA, 0x1B, 0x01, 0x26]
+        );
+    }
+
+    #[test]
+    fn rejects_join_accept_with_wrong_mic() {
+        let mut data = JOIN_ACCEPT;
+        data[16] ^= 1;
+        assert!(matches!(
+            extract_state(&mut data, APP_KEY),
+            Err(PacketError::MICMismatch)
+        ));
+
+        let mut data = JOIN_ACCEPT;
+        assert!(matches!(
+            extract_state(&mut data, APP_KEY ^ 1),
+            Err(PacketError::MICMismatch)
+        ));
+    }
+
+    fn device_state<R: RegionalParameters>(settings: Settings<R>, tx_dr: usize) -> DeviceState<R> {
+        let session = Session::new(DevAddr::new(0), NwkSKey::new(0), AppSKey::new(0));
+        let mut state = DeviceState::new(session, settings);
+        state.set_tx_dr(tx_dr);
+        state
+    }
+
+    #[test]
+    fn limits_payload_to_data_rate() {
+        // N is M minus the frame header and port, e.g. 11 bytes at DR0 of US915
+        let mut state = device_state(Settings::default_for(US915), 0);
+        assert!(Uplink::new(&[0; 11], 1, &mut state).is_ok());
+        assert!(matches!(
+            Uplink::new(&[0; 12], 1, &mut state),
+            Err(PacketError::PayloadTooLarge(11))
+        ));
+
+        // The data rate is only raised once the network no longer controls it with ADR
+        state.set_raise_data_rate(true);
+        assert!(matches!(
+            Uplink::new(&[0; 12], 1, &mut state),
+            Err(PacketError::PayloadTooLarge(11))
+        ));
+
+        state.set_adr(false);
+        let uplink = Uplink::new(&[0; 12], 1, &mut state).unwrap();
+        assert_eq!(uplink.data_rate(), 1);
+        assert_eq!(uplink.as_bytes()[F_CTRL_OFFSET] & 0x80, 0);
+    }
+
+    #[test]
+    fn rejects_uplinks_on_unusable_data_rates() {
+        // DR0 of CN470 has an M of zero
+        let mut state = device_state(Settings::default_for(CN470_20A), 0);
+        assert!(matches!(
+            Uplink::new(&[], 1, &mut state),
+            Err(PacketError::UnusableDataRate(0))
+        ));
+        state.set_adr(false);
+        state.set_raise_data_rate(true);
+        assert_eq!(Uplink::new(&[], 1, &mut state).unwrap().data_rate(), 1);
+
+        // DR7 of US915 is reserved
+        let mut state = device_state(Settings::default_for(US915), 7);
+        assert!(matches!(
+            Uplink::new(&[], 1, &mut state),
+            Err(PacketError::UnusableDataRate(7))
+        ));
+
+        // DR4 of US915 is only supported on the 500 kHz channels
+        let mut settings = Settings::default_for(US915);
+        settings.set_channel_mask(ChannelMask::range(8, 8));
+        let mut state = device_state(settings, 4);
+        state.set_adr(false);
+        state.set_raise_data_rate(true);
+        assert!(matches!(
+            Uplink::new(&[], 1, &mut state),
+            Err(PacketError::UnusableDataRate(4))
+        ));
+    }
+
+    #[test]
+    fn refuses_dr0_and_dr1_under_dwell_time() {
+        let regions: [&dyn RegionalParameters; 2] = [&AU915, &AS923_1];
+        for region in regions {
+            for dr in [0, 1] {
+                let mut state = device_state(Settings::default_for(region), dr);
+                assert!(matches!(
+                    Uplink::new(&[], 0, &mut state),
+                    Err(PacketError::UnusableDataRate(unusable)) if unusable == dr
+                ));
+
+                state.set_adr(false);
+                state.set_raise_data_rate(true);
+                assert_eq!(Uplink::new(&[], 0, &mut state).unwrap().data_rate(), 2);
+
+                state.set_tx_params(&TxParamSetupReq::new(&[0x00]));
+                state.set_raise_data_rate(false);
+                assert_eq!(Uplink::new(&[], 0, &mut state).unwrap().data_rate(), dr);
+            }
+        }
+    }
+
+    /// Builds a downlink with frame counter `fcnt` for the device of [device_state], with the MAC
+    /// commands in `f_opts` and `payload` on port 1.
+    fn downlink(
+        f_opts: &MacAnswers,
+        port: u8,
+        payload: &[u8],
+        fcnt: u32,
+    ) -> ([u8; MAX_PACKET_SIZE], usize) {
+        let mut phy = DataPayloadCreator::new();
+        phy.set_confirmed(false);
+        phy.set_dev_addr(DevAddr::new(0).as_bytes());
+        phy.set_f_port(port);
+        phy.set_fcnt(fcnt);
+        phy.set_fctrl(&FCtrl::new(0, false));
+        phy.set_uplink(false);
+        let f_opts: &[&dyn SerializableMacCommand] = &[f_opts];
+        let nwk_skey = (*NwkSKey::new(0).as_bytes()).into();
+        let app_skey = (*AppSKey::new(0).as_bytes()).into();
+        let data = phy.build(payload, f_opts, &nwk_skey, &app_skey).unwrap();
+
+        let mut buf = [0; MAX_PACKET_SIZE];
+        buf[..data.len()].copy_from_slice(data);
+        (buf, data.len())
+    }
+
+    #[test]
+    fn refuses_dl_channel_req_and_keeps_payload() {
+        let mut state = device_state(Settings::default_for(EU868), 0);
+        let mut f_opts = MacAnswers::default();
+        assert!(f_opts.push(mac::DL_CHANNEL, &[0x03, 0x18, 0x4F, 0x84]));
+        assert!(f_opts.push(mac::DEV_STATUS, &[]));
+
+        let (mut buf, len) = downlink(&f_opts, 1, b"hello", 0);
+        let downlink = Downlink::from_data(&mut buf[..len], &mut state).unwrap();
+        assert_eq!(downlink.as_bytes(), b"hello");
+        assert_eq!(state.fcnt_down(), 1);
+
+        let mut answers = MacAnswers::default();
+        assert!(answers.push(mac::DL_CHANNEL, &[0b00]));
+        assert_eq!(state.mac_answers().payload_bytes(), answers.payload_bytes());
+    }
+
+    #[test]
+    fn rejects_whole_frame_on_invalid_port() {
+        let mut state = device_state(Settings::default_for(AU915), 2);
+        let mut f_opts = MacAnswers::default();
+        assert!(f_opts.push(mac::TX_PARAM_SETUP, &[0x00]));
+
+        let (mut buf, len) = downlink(&f_opts, 224, b"hello", 0);
+        assert!(matches!(
+            Downlink::from_data(&mut buf[..len], &mut state),
+            Err(PacketError::InvalidPort(224))
+        ));
+        assert!(state.uplink_dwell_time());
+        assert!(state.mac_answers().is_empty());
+        // The frame was authentic, so it can not be replayed
+        assert_eq!(state.fcnt_down(), 1);
+    }
+
+    #[test]
+    fn only_applies_requests_whose_answer_is_queued() {
+        let mut state = device_state(Settings::default_for(AU915), 2);
+        for _ in 0..15 {
+            assert!(state.mac_answers_mut().push(mac::LINK_CHECK, &[]));
+        }
+
+        let commands = [
+            mac::TX_PARAM_SETUP,
+            0x00,
+            mac::BEACON_FREQ,
+            0xD2,
+            0xAD,
+            0x84,
+            mac::PING_SLOT_CHANNEL,
+            0xD2,
+            0xAD,
+            0x84,
+            0x03,
+        ];
+        Downlink::apply_mac_commands(&commands, &mut state);
+        assert!(state.uplink_dwell_time());
+        assert_eq!(state.beacon_frequency(), None);
+        assert_eq!(state.ping_slot_dr(), None);
+
+        state.mac_answers_mut().clear();
+        Downlink::apply_mac_commands(&commands, &mut state);
+        assert!(!state.uplink_dwell_time());
+        assert_eq!(state.beacon_frequency(), Some(869_525_000));
+        assert_eq!(state.ping_slot_dr(), Some(3));
+        assert_eq!(state.mac_answers().len(), 5);
+    }
+
+    #[test]
+    fn adds_and_removes_channels() {
+        let mut state = device_state(Settings::default_for(EU868), 7);
+        assert!(Uplink::new(&[], 1, &mut state).is_err());
+
+        // Channel 3 at 867.1 MHz for DR0 to DR7, which default channel 2 can not be changed to
+        let commands = [
+            mac::NEW_CHANNEL,
+            0x03,
+            0x18,
+            0x4F,
+            0x84,
+            0x70,
+            mac::NEW_CHANNEL,
+            0x02,
+            0x18,
+            0x4F,
+            0x84,
+            0x70,
+        ];
+        Downlink::apply_mac_commands(&commands, &mut state);
+        let mut answers = MacAnswers::default();
+        assert!(answers.push(mac::NEW_CHANNEL, &[0b11]));
+        assert!(answers.push(mac::NEW_CHANNEL, &[0b00]));
+        assert_eq!(state.mac_answers().payload_bytes(), answers.payload_bytes());
+
+        let settings = state.settings();
+        assert!(settings.channel_mask().is_enabled(3));
+        assert_eq!(settings.select_channel(7, 0), Some(3));
+        assert!(matches!(
+            settings.tx_channel(7, 3),
+            Some(ChannelConfig::Fsk(fsk)) if fsk.freq_khz == 867_100
+        ));
+        assert_eq!(Uplink::new(&[], 1, &mut state).unwrap().data_rate(), 7);
+
+        let commands = [mac::NEW_CHANNEL, 0x03, 0x00, 0x00, 0x00, 0x00];
+        Downlink::apply_mac_commands(&commands, &mut state);
+        assert!(!state.settings().channel_mask().is_enabled(3));
+        assert!(state.settings().added_channel(3).is_none());
+        assert!(Uplink::new(&[], 1, &mut state).is_err());
+    }
+
+    #[test]
+    fn sends_device_mode_ind_in_next_uplink() {
+        // FOpts follow MHDR, DevAddr, FCtrl and FCnt, and their length is in FCtrl
+        let mut state = device_state(Settings::default_for(EU868), 0);
+        state.announce_device_mode(DeviceClass::C);
+        assert_eq!(
+            Uplink::new(&[], 1, &mut state).unwrap().as_bytes()[5] & 0x0F,
+            0
+        );
+
+        state.set_mac_version(MacVersion::V1_1);
+        state.announce_device_mode(DeviceClass::C);
+        let uplink = Uplink::new(&[], 1, &mut state).unwrap();
+        assert_eq!(uplink.as_bytes()[5] & 0x0F, 2);
+        assert_eq!(uplink.as_bytes()[8..10], [mac::DEVICE_MODE, 0x02]);
+
+        state.announce_device_mode(DeviceClass::A);
+        let uplink = Uplink::new(&[], 1, &mut state).unwrap();
+        assert_eq!(uplink.as_bytes()[5] & 0x0F, 2);
+        assert_eq!(uplink.as_bytes()[8..10], [mac::DEVICE_MODE, 0x00]);
+    }
+
+    #[test]
+    fn repeats_device_mode_ind_until_confirmed() {
+        let mut state = device_state(Settings::default_for(EU868), 0);
+        state.set_mac_version(MacVersion::V1_1);
+        state.announce_device_mode(DeviceClass::C);
+
+        // The uplink was sent, but no DeviceModeConf came back
+        Uplink::new(&[], 1, &mut state).unwrap();
+        state.mac_answers_mut().clear();
+        let uplink = Uplink::new(&[], 1, &mut state).unwrap();
+        assert_eq!(uplink.as_bytes()[8..10], [mac::DEVICE_MODE, 0x02]);
+
+        // A DeviceModeConf for another class does not confirm the switch
+        Downlink::apply_mac_commands(&[mac::DEVICE_MODE, 0x00], &mut state);
+        assert_eq!(state.pending_device_mode(), Some(DeviceClass::C));
+
+        // The DeviceModeConf is accepted without an answer
+        Downlink::apply_mac_commands(&[mac::DEVICE_MODE, 0x02], &mut state);
+        assert_eq!(state.pending_device_mode(), None);
+        assert!(state.mac_answers().is_empty());
+        let uplink = Uplink::new(&[], 1, &mut state).unwrap();
+        assert_eq!(uplink.as_bytes()[5] & 0x0F, 0);
+    }
+}
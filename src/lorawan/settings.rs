@@ -1,31 +1,127 @@
 use crate::lorawan::RECEIVE_DELAY;
-use core::marker::PhantomData;
+use crate::radio::{AddedChannel, CfListType, ChannelConfig, ChannelMask, RegionalParameters};
 use core::time::Duration;
 
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct Settings<R> {
+    region: R,
     rx_delay: Duration,
     rx1_dr_offset: usize,
     rx2_dr: usize,
-    _region: PhantomData<R>,
+    channel_mask: ChannelMask,
+    added_channels: [Option<AddedChannel>; AddedChannel::MAX_CHANNELS],
+    max_eirp: i8,
 }
 
-impl<R> Settings<R> {
-    pub fn new(rx_delay: u8, rx1_dr_offset: u8, rx2_dr: u8) -> Self {
+impl<R: RegionalParameters> Settings<R> {
+    pub fn new(region: R, rx_delay: u8, rx1_dr_offset: u8, rx2_dr: u8) -> Self {
         let rx_delay = Duration::from_secs(match rx_delay & 0x0F {
             0 => 1,
             n => n as u64,
         });
 
         Settings {
+            channel_mask: region.default_channel_mask(),
+            added_channels: [None; AddedChannel::MAX_CHANNELS],
+            max_eirp: region.max_eirp(),
+            region,
             rx_delay,
             rx1_dr_offset: rx1_dr_offset as usize,
             rx2_dr: rx2_dr as usize,
-            _region: PhantomData,
         }
     }
 
+    /// Creates the default settings of `region`, which apply until the network configures others.
+    pub fn default_for(region: R) -> Self {
+        Settings {
+            rx_delay: RECEIVE_DELAY,
+            rx1_dr_offset: 0,
+            rx2_dr: region.rx2_data_rate(),
+            channel_mask: region.default_channel_mask(),
+            added_channels: [None; AddedChannel::MAX_CHANNELS],
+            max_eirp: region.max_eirp(),
+            region,
+        }
+    }
+
+    /// Adds `channel` at index `index` and enables it, or removes the channel at that index if
+    /// `channel` is `None`. Returns `false` if the region does not let the network add channels,
+    /// or if `index` is that of a default channel.
+    pub fn set_added_channel(&mut self, index: usize, channel: Option<AddedChannel>) -> bool {
+        let added = self.region.channel_count()..AddedChannel::MAX_CHANNELS;
+        if self.region.cf_list_type() != CfListType::Frequencies || !added.contains(&index) {
+            return false;
+        }
+
+        self.added_channels[index] = channel;
+        self.channel_mask.set(index, channel.is_some());
+        true
+    }
+
+    /// Whether uplink channel `channel`, which is either a default channel of the region or one
+    /// the network added, may be used with data rate `dr`.
+    pub fn supports_data_rate(&self, channel: usize, dr: usize) -> bool {
+        if channel < self.region.channel_count() {
+            return self.region.supports_data_rate(channel, dr);
+        }
+
+        self.added_channel(channel)
+            .is_some_and(|added| added.supports_data_rate(dr))
+            && dr <= self.region.max_tx_data_rate()
+            && self.region.data_rate(dr).is_some()
+    }
+
+    /// Picks one of the enabled channels that supports data rate `dr`, using `noise` to spread
+    /// uplinks over them. Unlike [RegionalParameters::select_channel], this includes the channels
+    /// the network added.
+    pub fn select_channel(&self, dr: usize, noise: usize) -> Option<usize> {
+        let channels = || {
+            self.channel_mask
+                .channels()
+                .filter(move |&channel| self.supports_data_rate(channel, dr))
+        };
+        let count = channels().count();
+        if count == 0 {
+            None
+        } else {
+            channels().nth(noise % count)
+        }
+    }
+
+    /// Returns the channel to transmit on at data rate `dr` on uplink channel `channel`.
+    pub fn tx_channel(&self, dr: usize, channel: usize) -> Option<ChannelConfig> {
+        match self.added_channel(channel) {
+            Some(added) => {
+                let data_rate = self.region.data_rate(dr)?;
+                Some(self.region.channel(data_rate, added.frequency()))
+            }
+            None => self.region.tx_channel(dr, channel),
+        }
+    }
+
+    /// Returns the channel of RX1 at data rate `dr`, after an uplink on channel `channel`. The
+    /// network answers on the uplink frequency of the channels it added.
+    pub fn rx1_channel(&self, dr: usize, channel: usize) -> Option<ChannelConfig> {
+        match self.added_channel(channel) {
+            Some(added) => {
+                let data_rate = self
+                    .region
+                    .data_rate(dr)
+                    .filter(|dr| !dr.is_uplink_only())?;
+                Some(self.region.channel(data_rate, added.frequency()))
+            }
+            None => self.region.rx1_channel(dr, channel),
+        }
+    }
+}
+
+impl<R> Settings<R> {
+    /// The region the device operates in.
+    pub fn region(&self) -> &R {
+        &self.region
+    }
+
     pub fn rx_delay(&self) -> Duration {
         self.rx_delay
     }
@@ -37,15 +133,27 @@ impl<R> Settings<R> {
     pub fn rx2_dr(&self) -> usize {
         self.rx2_dr
     }
-}
 
-impl<R> Default for Settings<R> {
-    fn default() -> Self {
-        Settings {
-            rx_delay: RECEIVE_DELAY,
-            rx1_dr_offset: 0,
-            rx2_dr: 0,
-            _region: PhantomData,
-        }
+    /// The uplink channels the device may use.
+    pub fn channel_mask(&self) -> &ChannelMask {
+        &self.channel_mask
+    }
+
+    pub fn set_channel_mask(&mut self, channel_mask: ChannelMask) {
+        self.channel_mask = channel_mask;
+    }
+
+    /// Returns the channel the network added at index `index`, if any.
+    pub fn added_channel(&self, index: usize) -> Option<&AddedChannel> {
+        self.added_channels.get(index)?.as_ref()
+    }
+
+    /// The maximum EIRP in dBm the device may transmit with.
+    pub fn max_eirp(&self) -> i8 {
+        self.max_eirp
+    }
+
+    pub fn set_max_eirp(&mut self, max_eirp: i8) {
+        self.max_eirp = max_eirp;
     }
 }
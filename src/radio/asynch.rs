@@ -0,0 +1,300 @@
+use core::fmt::Debug;
+use core::time::Duration;
+
+use embedded_hal_async::delay::DelayNs;
+use radio::modulation::lora::LoRaChannel;
+use rand_core::RngCore;
+
+use crate::lorawan::Settings;
+use crate::radio::{
+    BeaconChannel, ChannelConfig, Clock, FskChannel, ListenBeforeTalk, LoRaInfo, LoRaRadio,
+    LrFhssChannel, RadioChannel, RadioError, RegionalParameters,
+};
+
+/// An async radio, which can be used instead of the blocking radio-hal traits to let other tasks
+/// run while waiting for the radio.
+///
+/// Its provided methods take the place of [RadioHooks], so the async methods are only available
+/// on radios created without hooks.
+///
+/// [RadioHooks]: crate::radio::RadioHooks
+#[allow(async_fn_in_trait)]
+pub trait AsyncRadio {
+    type Error: Debug;
+    type Channel: RadioChannel;
+    type Info: Into<LoRaInfo>;
+
+    async fn set_channel(&mut self, channel: &Self::Channel) -> Result<(), Self::Error>;
+
+    /// Transmits `data`, and completes when the transmission has finished.
+    async fn transmit(&mut self, data: &[u8]) -> Result<(), Self::Error>;
+
+    /// Listens for a message, and stores it in `buf`. Returns `None` if the radio has not started
+    /// receiving a message within `timeout`.
+    async fn receive(
+        &mut self,
+        buf: &mut [u8],
+        timeout: Duration,
+    ) -> Result<Option<(usize, Self::Info)>, Self::Error>;
+
+    /// Listens on the current channel, and returns the RSSI in dBm, or `None` if the radio can not
+    /// measure it. Listen-before-talk requires this. The default implementation returns `None`.
+    async fn rssi(&mut self) -> Result<Option<i16>, Self::Error> {
+        Ok(None)
+    }
+
+    /// Sets the transmit power for an EIRP of `eirp` dBm, i.e. to `eirp` minus the gain of the
+    /// antenna. The default implementation does nothing, so the radio keeps its own power.
+    async fn set_power(&mut self, _eirp: i8) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Puts the radio to sleep or in standby while the device waits for a receive window. The
+    /// channel of the window is configured before, so the radio must keep its configuration. It is
+    /// woken up by the next operation. The default implementation does nothing.
+    async fn sleep(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Converts an FSK channel, or returns `None` if the radio does not support FSK.
+    fn fsk_channel(&self, _channel: FskChannel) -> Option<Self::Channel> {
+        None
+    }
+
+    /// Converts an LR-FHSS channel, or returns `None` if the radio does not support LR-FHSS.
+    fn lr_fhss_channel(&self, _channel: LrFhssChannel) -> Option<Self::Channel> {
+        None
+    }
+
+    /// Converts a LoRa channel that uses long interleaving, or returns `None` if the radio does
+    /// not support it.
+    fn long_interleaving_channel(&self, _channel: LoRaChannel) -> Option<Self::Channel> {
+        None
+    }
+
+    /// Converts the channel of a class B beacon, or returns `None` if the radio can not receive
+    /// packets with an implicit header.
+    fn beacon_channel(&self, _channel: BeaconChannel) -> Option<Self::Channel> {
+        None
+    }
+}
+
+impl<RXTX, TIM, RNG, ERR> LoRaRadio<RXTX, TIM, RNG, ERR>
+where
+    RXTX: AsyncRadio<Error = ERR>,
+    TIM: DelayNs + Clock,
+    RNG: RngCore,
+    ERR: Debug,
+{
+    /// The async version of [LoRaRadio::lorawan_transmit].
+    pub async fn lorawan_transmit_async<R: RegionalParameters>(
+        &mut self,
+        tx: &[u8],
+        rx: &mut [u8],
+        tx_dr: usize,
+        dwell_time: bool,
+        settings: &Settings<R>,
+    ) -> Result<Option<(usize, LoRaInfo)>, RadioError<ERR>> {
+        self.lorawan_transmit_delayed_async(
+            tx,
+            rx,
+            tx_dr,
+            dwell_time,
+            settings.rx_delay(),
+            settings,
+        )
+        .await
+    }
+
+    /// The async version of [LoRaRadio::lorawan_transmit_delayed].
+    pub async fn lorawan_transmit_delayed_async<R: RegionalParameters>(
+        &mut self,
+        tx: &[u8],
+        rx: &mut [u8],
+        tx_dr: usize,
+        dwell_time: bool,
+        delay: Duration,
+        settings: &Settings<R>,
+    ) -> Result<Option<(usize, LoRaInfo)>, RadioError<ERR>> {
+        self.transmit_and_receive_async(tx, rx, tx_dr, dwell_time, delay, settings, false)
+            .await
+    }
+
+    /// The async version of [LoRaRadio::lorawan_transmit_class_c]. The radio only listens on RX2
+    /// until the end of the RX2 window; afterwards, [lorawan_listen_async] listens again.
+    ///
+    /// [lorawan_listen_async]: LoRaRadio::lorawan_listen_async
+    pub async fn lorawan_transmit_class_c_async<R: RegionalParameters>(
+        &mut self,
+        tx: &[u8],
+        rx: &mut [u8],
+        tx_dr: usize,
+        dwell_time: bool,
+        settings: &Settings<R>,
+    ) -> Result<Option<(usize, LoRaInfo)>, RadioError<ERR>> {
+        let delay = settings.rx_delay();
+        self.transmit_and_receive_async(tx, rx, tx_dr, dwell_time, delay, settings, true)
+            .await
+    }
+
+    /// The async version of [LoRaRadio::lorawan_listen]. The radio only listens while this runs.
+    pub async fn lorawan_listen_async<R: RegionalParameters>(
+        &mut self,
+        rx: &mut [u8],
+        timeout: Duration,
+        settings: &Settings<R>,
+    ) -> Result<Option<(usize, LoRaInfo)>, RadioError<ERR>> {
+        let rx2_channel = settings
+            .region()
+            .rx2_channel(settings.rx2_dr())
+            .ok_or(RadioError::UnsupportedDataRate)?;
+        self.set_channel_async(rx2_channel).await?;
+        self.receive_async(rx, timeout).await
+    }
+
+    /// The async version of [LoRaRadio::transmit_and_receive].
+    #[allow(clippy::too_many_arguments)]
+    async fn transmit_and_receive_async<R: RegionalParameters>(
+        &mut self,
+        tx: &[u8],
+        rx: &mut [u8],
+        tx_dr: usize,
+        dwell_time: bool,
+        delay: Duration,
+        settings: &Settings<R>,
+        class_c: bool,
+    ) -> Result<Option<(usize, LoRaInfo)>, RadioError<ERR>> {
+        let channel = self.select_channel_async(settings, tx_dr).await?;
+        let windows =
+            self.response_windows(settings, tx_dr, dwell_time, delay, channel, Duration::ZERO)?;
+
+        #[cfg(feature = "defmt")]
+        defmt::trace!("transmitting LoRaWAN packet");
+        let tx_channel = settings
+            .tx_channel(tx_dr, channel)
+            .ok_or(RadioError::UnsupportedDataRate)?;
+        self.set_channel_async(tx_channel).await?;
+        self.radio.set_power(settings.max_eirp()).await?;
+        let tx_start = self.tim.now();
+        self.radio.transmit(tx).await?;
+        let tx_done = self.tim.now();
+        self.durations.add_transmitting(tx_done - tx_start);
+
+        for listen in windows.listens(tx_done, class_c) {
+            self.set_channel_async(listen.channel.clone()).await?;
+            if let Some(start) = listen.start {
+                self.sleep_until_async(start).await?;
+            }
+
+            #[cfg(feature = "defmt")]
+            defmt::trace!("receiving on {}", listen.name);
+            let timeout = listen.timeout(self.tim.now());
+            if let Some(response) = self.receive_async(rx, timeout).await? {
+                #[cfg(feature = "defmt")]
+                defmt::trace!("response received");
+                return Ok(Some(response));
+            }
+        }
+
+        #[cfg(feature = "defmt")]
+        defmt::trace!("no response");
+        Ok(None)
+    }
+
+    /// The async version of [LoRaRadio::select_channel].
+    async fn select_channel_async<R: RegionalParameters>(
+        &mut self,
+        settings: &Settings<R>,
+        dr: usize,
+    ) -> Result<usize, RadioError<ERR>> {
+        let lbt = match settings.region().listen_before_talk() {
+            None => return self.random_channel(settings, dr),
+            Some(lbt) => lbt,
+        };
+
+        for _ in 0..Self::LBT_ATTEMPTS {
+            let (channel, tx_channel) = self.lbt_candidate(settings, dr)?;
+            if self.is_channel_free_async(tx_channel, lbt).await? {
+                return Ok(channel);
+            }
+
+            #[cfg(feature = "defmt")]
+            defmt::trace!("channel {} is busy", channel);
+        }
+
+        Err(RadioError::ChannelBusy)
+    }
+
+    /// The async version of [LoRaRadio::is_channel_free].
+    async fn is_channel_free_async(
+        &mut self,
+        channel: ChannelConfig,
+        lbt: &ListenBeforeTalk,
+    ) -> Result<bool, RadioError<ERR>> {
+        self.set_channel_async(channel).await?;
+
+        let start = self.tim.now();
+        let mut time = Duration::ZERO;
+        let free = loop {
+            if time >= lbt.duration() {
+                break true;
+            }
+            self.tim
+                .delay_us(Self::LBT_INTERVAL.as_micros() as u32)
+                .await;
+            let rssi = self
+                .radio
+                .rssi()
+                .await?
+                .ok_or(RadioError::UnsupportedListenBeforeTalk)?;
+            if rssi >= lbt.threshold() {
+                break false;
+            }
+            time += Self::LBT_INTERVAL;
+        };
+
+        self.durations.add_receiving(self.tim.now() - start);
+        Ok(free)
+    }
+
+    /// Listens for a message for up to `timeout`, and stores it in `rx`.
+    async fn receive_async(
+        &mut self,
+        rx: &mut [u8],
+        timeout: Duration,
+    ) -> Result<Option<(usize, LoRaInfo)>, RadioError<ERR>> {
+        let start = self.tim.now();
+        let response = self.radio.receive(rx, timeout).await?;
+        self.durations.add_receiving(self.tim.now() - start);
+        Ok(response.map(|(n, info)| (n, info.into())))
+    }
+
+    /// The async version of [LoRaRadio::set_channel].
+    async fn set_channel_async(&mut self, channel: ChannelConfig) -> Result<(), RadioError<ERR>> {
+        let channel = match channel {
+            ChannelConfig::LoRa(channel) => Some(channel.into()),
+            ChannelConfig::Fsk(channel) => self.radio.fsk_channel(channel),
+            ChannelConfig::LrFhss(channel) => self.radio.lr_fhss_channel(channel),
+            ChannelConfig::LongInterleaving(channel) => {
+                self.radio.long_interleaving_channel(channel)
+            }
+            ChannelConfig::Beacon(channel) => self.radio.beacon_channel(channel),
+        }
+        .ok_or(RadioError::UnsupportedDataRate)?;
+        self.radio.set_channel(&channel).await?;
+        Ok(())
+    }
+
+    /// The async version of [LoRaRadio::sleep_until]. The MCU sleeps whenever the executor has
+    /// nothing to run.
+    async fn sleep_until_async(&mut self, instant: Duration) -> Result<(), RadioError<ERR>> {
+        let instant = instant.saturating_sub(self.wake_up_time);
+        let now = self.tim.now();
+        if instant > now {
+            self.radio.sleep().await?;
+            self.tim.delay_us((instant - now).as_micros() as u32).await;
+            self.durations.add_sleeping(self.tim.now() - now);
+        }
+        Ok(())
+    }
+}
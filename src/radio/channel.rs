@@ -0,0 +1,216 @@
+use crate::radio::Hz;
+
+/// The set of uplink channels a device may use, indexed like [TX_FREQUENCIES].
+///
+/// [TX_FREQUENCIES]: crate::radio::Region::TX_FREQUENCIES
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ChannelMask(u128);
+
+impl ChannelMask {
+    /// The highest number of channels a mask can describe.
+    pub const MAX_CHANNELS: usize = 128;
+
+    /// Creates a mask with no channels enabled.
+    pub const fn none() -> Self {
+        ChannelMask(0)
+    }
+
+    /// Creates a mask with the first `count` channels enabled.
+    pub const fn first(count: usize) -> Self {
+        if count >= Self::MAX_CHANNELS {
+            ChannelMask(u128::MAX)
+        } else {
+            ChannelMask((1 << count) - 1)
+        }
+    }
+
+    /// Creates a mask with `count` channels enabled, starting at `first`.
+    pub const fn range(first: usize, count: usize) -> Self {
+        if first >= Self::MAX_CHANNELS {
+            ChannelMask::none()
+        } else {
+            ChannelMask(Self::first(count).0 << first)
+        }
+    }
+
+    /// Creates a mask enabling the eight 125 kHz channels and the 500 kHz channel of a sub-band, in
+    /// regions with 64 + 8 uplink channels.
+    pub const fn sub_band(sub_band: SubBand) -> Self {
+        let index = sub_band as usize;
+        ChannelMask((0xFF << (8 * index)) | (1 << (64 + index)))
+    }
+
+    /// Parses a CFList of type 1, which consists of five 16-bit channel masks.
+    pub fn from_cf_list(cf_list: &[u8; 15]) -> Self {
+        let mut mask = ChannelMask::none();
+        for (block, bytes) in cf_list[..10].chunks(2).enumerate() {
+            mask.set_block(block, u16::from_le_bytes([bytes[0], bytes[1]]));
+        }
+        mask
+    }
+
+    pub fn is_enabled(&self, channel: usize) -> bool {
+        channel < Self::MAX_CHANNELS && self.0 & (1 << channel) != 0
+    }
+
+    pub fn set(&mut self, channel: usize, enabled: bool) {
+        if channel < Self::MAX_CHANNELS {
+            if enabled {
+                self.0 |= 1 << channel;
+            } else {
+                self.0 &= !(1 << channel);
+            }
+        }
+    }
+
+    /// Replaces channels `16 * block` up to `16 * block + 15` with the bits in `mask`, as used by
+    /// the ChMask field of the CFList and LinkADRReq.
+    pub fn set_block(&mut self, block: usize, mask: u16) {
+        let shift = 16 * block;
+        if shift < Self::MAX_CHANNELS {
+            self.0 = (self.0 & !(0xFFFF << shift)) | ((mask as u128) << shift);
+        }
+    }
+
+    /// Returns the enabled channels in ascending order.
+    pub fn channels(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..Self::MAX_CHANNELS).filter(move |&channel| self.is_enabled(channel))
+    }
+}
+
+/// An uplink channel that the network added to the default channels of the region, with the
+/// CFList of a join-accept or a NewChannelReq.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AddedChannel {
+    frequency: Hz,
+    min_dr: usize,
+    max_dr: usize,
+}
+
+impl AddedChannel {
+    /// The number of channel indices in regions where the network adds channels, including the
+    /// default channels.
+    pub const MAX_CHANNELS: usize = 16;
+
+    /// Creates a channel at `frequency` that may be used with data rates `min_dr` to `max_dr`.
+    pub const fn new(frequency: Hz, min_dr: usize, max_dr: usize) -> Self {
+        AddedChannel {
+            frequency,
+            min_dr,
+            max_dr,
+        }
+    }
+
+    pub fn frequency(&self) -> Hz {
+        self.frequency
+    }
+
+    pub fn supports_data_rate(&self, dr: usize) -> bool {
+        (self.min_dr..=self.max_dr).contains(&dr)
+    }
+}
+
+/// A group of eight 125 kHz uplink channels and one 500 kHz uplink channel in regions with 64 + 8
+/// uplink channels. Gateways often only listen on one of these.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SubBand {
+    Fsb1,
+    Fsb2,
+    Fsb3,
+    Fsb4,
+    Fsb5,
+    Fsb6,
+    Fsb7,
+    Fsb8,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_channels(mask: &ChannelMask, channels: &[usize]) {
+        assert!(
+            mask.channels().eq(channels.iter().copied()),
+            "{:X?} != {:?}",
+            mask,
+            channels
+        );
+    }
+
+    #[test]
+    fn creates_masks() {
+        let cases: &[(ChannelMask, &[usize])] = &[
+            (ChannelMask::none(), &[]),
+            (ChannelMask::first(0), &[]),
+            (ChannelMask::first(3), &[0, 1, 2]),
+            (ChannelMask::range(5, 3), &[5, 6, 7]),
+            (ChannelMask::range(126, 4), &[126, 127]),
+            (ChannelMask::range(128, 4), &[]),
+            (
+                ChannelMask::sub_band(SubBand::Fsb1),
+                &[0, 1, 2, 3, 4, 5, 6, 7, 64],
+            ),
+            (
+                ChannelMask::sub_band(SubBand::Fsb2),
+                &[8, 9, 10, 11, 12, 13, 14, 15, 65],
+            ),
+            (
+                ChannelMask::sub_band(SubBand::Fsb5),
+                &[32, 33, 34, 35, 36, 37, 38, 39, 68],
+            ),
+            (
+                ChannelMask::sub_band(SubBand::Fsb8),
+                &[56, 57, 58, 59, 60, 61, 62, 63, 71],
+            ),
+        ];
+        for (mask, channels) in cases {
+            assert_channels(mask, channels);
+        }
+        assert_eq!(
+            ChannelMask::first(200).channels().count(),
+            ChannelMask::MAX_CHANNELS
+        );
+    }
+
+    #[test]
+    fn sets_channels_and_blocks() {
+        let mut mask = ChannelMask::first(4);
+        mask.set(1, false);
+        mask.set(10, true);
+        mask.set(ChannelMask::MAX_CHANNELS, true);
+        assert_channels(&mask, &[0, 2, 3, 10]);
+        assert!(!mask.is_enabled(ChannelMask::MAX_CHANNELS));
+
+        mask.set_block(0, 0x8001);
+        mask.set_block(4, 0x0003);
+        mask.set_block(8, 0xFFFF);
+        assert_channels(&mask, &[0, 15, 64, 65]);
+    }
+
+    #[test]
+    fn parses_cf_list() {
+        let cases: &[([u8; 15], &[usize])] = &[
+            ([0; 15], &[]),
+            (
+                [0xFF, 0x00, 0, 0, 0, 0, 0, 0, 0x01, 0x00, 0, 0, 0, 0, 1],
+                &[0, 1, 2, 3, 4, 5, 6, 7, 64],
+            ),
+            (
+                [0x00, 0xFF, 0, 0, 0, 0, 0, 0, 0x02, 0x00, 0, 0, 0, 0, 1],
+                &[8, 9, 10, 11, 12, 13, 14, 15, 65],
+            ),
+            (
+                [
+                    0x01, 0x00, 0x00, 0x80, 0x02, 0x00, 0, 0, 0, 0x01, 0xFF, 0xFF, 0xFF, 0, 1,
+                ],
+                &[0, 31, 33, 72],
+            ),
+        ];
+        for (cf_list, channels) in cases {
+            assert_channels(&ChannelMask::from_cf_list(cf_list), channels);
+        }
+    }
+}
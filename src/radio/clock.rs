@@ -0,0 +1,106 @@
+use core::time::Duration;
+
+use embedded_hal::blocking::delay::DelayUs;
+#[cfg(feature = "async")]
+use embedded_hal_async::delay::DelayNs;
+
+/// A monotonic clock, e.g. backed by a hardware timer or RTC. It is used to time the receive
+/// windows relative to the end of a transmission.
+pub trait Clock {
+    /// Returns the time elapsed since an arbitrary fixed point, such as boot. It must never
+    /// decrease.
+    fn now(&mut self) -> Duration;
+}
+
+impl<F: FnMut() -> Duration> Clock for F {
+    fn now(&mut self) -> Duration {
+        self()
+    }
+}
+
+/// Combines a delay and a clock into the timer of a [LoRaRadio], for HALs whose delays do not
+/// keep time. The clock can be a closure, e.g. one reading a monotonic timer.
+///
+/// [LoRaRadio]: crate::radio::LoRaRadio
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DelayClock<D, C> {
+    delay: D,
+    clock: C,
+}
+
+impl<D, C> DelayClock<D, C> {
+    pub fn new(delay: D, clock: C) -> Self {
+        DelayClock { delay, clock }
+    }
+
+    pub fn as_delay(&self) -> &D {
+        &self.delay
+    }
+
+    pub fn as_mut_delay(&mut self) -> &mut D {
+        &mut self.delay
+    }
+
+    pub fn as_clock(&self) -> &C {
+        &self.clock
+    }
+
+    pub fn as_mut_clock(&mut self) -> &mut C {
+        &mut self.clock
+    }
+
+    pub fn into_parts(self) -> (D, C) {
+        (self.delay, self.clock)
+    }
+}
+
+impl<D, C: Clock> Clock for DelayClock<D, C> {
+    fn now(&mut self) -> Duration {
+        self.clock.now()
+    }
+}
+
+impl<D: DelayUs<u32>, C> DelayUs<u32> for DelayClock<D, C> {
+    fn delay_us(&mut self, us: u32) {
+        self.delay.delay_us(us);
+    }
+}
+
+#[cfg(feature = "async")]
+impl<D: DelayNs, C> DelayNs for DelayClock<D, C> {
+    async fn delay_ns(&mut self, ns: u32) {
+        self.delay.delay_ns(ns).await;
+    }
+
+    async fn delay_us(&mut self, us: u32) {
+        self.delay.delay_us(us).await;
+    }
+
+    async fn delay_ms(&mut self, ms: u32) {
+        self.delay.delay_ms(ms).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Delay;
+
+    impl DelayUs<u32> for Delay {
+        fn delay_us(&mut self, _us: u32) {}
+    }
+
+    #[test]
+    fn combines_delay_and_closure() {
+        let mut ticks = 0;
+        let mut tim = DelayClock::new(Delay, || {
+            ticks += 1;
+            Duration::from_millis(ticks)
+        });
+        tim.delay_us(1000);
+        assert_eq!(tim.now(), Duration::from_millis(1));
+        assert_eq!(tim.now(), Duration::from_millis(2));
+    }
+}
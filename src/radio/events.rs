@@ -0,0 +1,12 @@
+/// An event signalled by a radio, e.g. through a DIO interrupt.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RadioEvent {
+    /// The radio finished transmitting.
+    TxDone,
+    /// The radio received a message.
+    RxDone,
+    /// The radio stopped receiving without a message, e.g. because of its own timeout or a CRC
+    /// error.
+    Timeout,
+}
@@ -0,0 +1,96 @@
+use core::time::Duration;
+
+use radio::modulation::lora::LoRaChannel;
+use radio::{Channel, Rssi};
+
+use crate::radio::{BeaconChannel, FskChannel, LrFhssChannel, RadioEvent};
+
+/// Optional features of a radio that the radio-hal traits do not cover. These are implemented by
+/// a type of the application, e.g. one that also holds the interrupt pins of the radio, and passed
+/// to [LoRaRadio::with_hooks]. Every method defaults to the behavior of a radio without the
+/// feature, so radios without any use [NoHooks].
+///
+/// [LoRaRadio::with_hooks]: crate::radio::LoRaRadio::with_hooks
+pub trait RadioHooks<RXTX: Channel> {
+    /// Blocks until `radio` signals an event, or until `timeout` has passed, e.g. by waiting for a
+    /// DIO interrupt. Returns `None` if no event was signalled. The default implementation returns
+    /// `None` immediately, so that the radio is polled instead.
+    fn wait_for_event(&mut self, _radio: &mut RXTX, _timeout: Duration) -> Option<RadioEvent> {
+        None
+    }
+
+    /// Measures the RSSI on the current channel of `radio` in dBm, or returns `None` if the radio
+    /// can not measure it. Listen-before-talk requires this.
+    fn rssi(&mut self, _radio: &mut RXTX) -> Option<Result<i16, RXTX::Error>> {
+        None
+    }
+
+    /// Sets the transmit power of `radio` for an EIRP of `eirp` dBm, i.e. to `eirp` minus the gain
+    /// of the antenna. The default implementation does nothing, so the radio keeps its own power.
+    fn set_power(&mut self, _radio: &mut RXTX, _eirp: i8) -> Result<(), RXTX::Error> {
+        Ok(())
+    }
+
+    /// Puts `radio` to sleep or in standby while the device waits for a receive window. The
+    /// channel of the window is configured before, so the radio must keep its configuration, e.g.
+    /// with a warm start. It is woken up by the next operation. The default implementation does
+    /// nothing.
+    fn sleep_radio(&mut self, _radio: &mut RXTX) -> Result<(), RXTX::Error> {
+        Ok(())
+    }
+
+    /// Puts the MCU in a low-power mode for `duration`, e.g. a stop mode that is woken up by an
+    /// RTC alarm. Returns `false` if it did not sleep, in which case the device busy-waits with
+    /// its delay instead. The default implementation returns `false`.
+    fn sleep(&mut self, _duration: Duration) -> bool {
+        false
+    }
+
+    /// Converts an FSK channel, or returns `None` if the radio does not support FSK.
+    fn fsk_channel(&mut self, _channel: FskChannel) -> Option<RXTX::Channel> {
+        None
+    }
+
+    /// Converts an LR-FHSS channel, or returns `None` if the radio does not support LR-FHSS.
+    fn lr_fhss_channel(&mut self, _channel: LrFhssChannel) -> Option<RXTX::Channel> {
+        None
+    }
+
+    /// Converts a LoRa channel that uses long interleaving, as in [ISM2400], or returns `None` if
+    /// the radio does not support it.
+    ///
+    /// [ISM2400]: crate::radio::ISM2400
+    fn long_interleaving_channel(&mut self, _channel: LoRaChannel) -> Option<RXTX::Channel> {
+        None
+    }
+
+    /// Converts the channel of a class B beacon, or returns `None` if the radio can not receive
+    /// packets with an implicit header. Class B requires this.
+    fn beacon_channel(&mut self, _channel: BeaconChannel) -> Option<RXTX::Channel> {
+        None
+    }
+}
+
+/// The hooks of a radio that only supports what the radio-hal traits cover.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct NoHooks;
+
+impl<RXTX: Channel> RadioHooks<RXTX> for NoHooks {}
+
+/// The hooks of a radio that can measure the RSSI, e.g. for listen-before-talk in [AS923], and
+/// otherwise only supports what the radio-hal traits cover.
+///
+/// [AS923]: crate::radio::AS923
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RssiHooks;
+
+impl<RXTX> RadioHooks<RXTX> for RssiHooks
+where
+    RXTX: Channel + Rssi<Error = <RXTX as Channel>::Error>,
+{
+    fn rssi(&mut self, radio: &mut RXTX) -> Option<Result<i16, <RXTX as Channel>::Error>> {
+        Some(radio.poll_rssi())
+    }
+}
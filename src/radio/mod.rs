@@ -3,29 +3,88 @@ use core::marker::PhantomData;
 use core::time::Duration;
 
 use embedded_hal::blocking::delay::DelayUs;
-use radio::modulation::lora::LoRaChannel;
 use radio::{BasicInfo, Busy, Channel, Receive, ReceiveInfo, Transmit};
 use rand_core::RngCore;
 
 use crate::lorawan::{Settings, NEXT_DELAY};
+#[cfg(feature = "async")]
+pub use crate::radio::asynch::*;
+pub use crate::radio::channel::*;
+pub use crate::radio::clock::*;
+pub use crate::radio::events::*;
+pub use crate::radio::hooks::*;
 pub use crate::radio::rate::*;
 pub use crate::radio::region::*;
-
+pub use crate::radio::sleep::*;
+pub use crate::radio::window::*;
+
+#[cfg(feature = "async")]
+mod asynch;
+mod channel;
+mod clock;
+mod events;
+mod hooks;
 mod rate;
 mod region;
+mod sleep;
+mod window;
 
 /// Combines all the traits necessary for LoRa into one struct, and provides useful methods to
 /// transmit messages.
 #[derive(Debug)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
-pub struct LoRaRadio<RXTX, TIM, RNG, ERR> {
+pub struct LoRaRadio<RXTX, TIM, RNG, ERR, H = NoHooks> {
     radio: RXTX,
     tim: TIM,
     rng: RNG,
+    hooks: H,
+    clock_drift: u32,
+    wake_up_time: Duration,
+    poll_interval: Duration,
+    durations: StateDurations,
+    listening: bool,
     err: PhantomData<ERR>,
 }
 
 impl<RXTX, TIM, RNG, ERR> LoRaRadio<RXTX, TIM, RNG, ERR> {
+    /// Constructs a new LoRa radio.
+    pub fn new(radio: RXTX, tim: TIM, rng: RNG) -> Self {
+        Self::with_hooks(radio, tim, rng, NoHooks)
+    }
+}
+
+impl<RXTX, TIM, RNG, ERR, H> LoRaRadio<RXTX, TIM, RNG, ERR, H> {
+    /// The largest error in the timing of the receive windows, apart from clock drift and from
+    /// polling the radio for the end of the transmission. The receive windows open this much
+    /// earlier, and stay open this much longer, to make up for it.
+    const CLOCK_ERROR: Duration = Duration::from_millis(3);
+
+    /// The shortest time between two polls of a radio that does not signal events.
+    const MIN_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+    /// How many channels to try before giving up, if the region requires listen-before-talk.
+    const LBT_ATTEMPTS: usize = 8;
+
+    /// How often to measure the RSSI while checking whether a channel is free.
+    const LBT_INTERVAL: Duration = Duration::from_micros(500);
+
+    /// Constructs a new LoRa radio, with `hooks` for features of the radio that the radio-hal
+    /// traits do not cover.
+    pub fn with_hooks(radio: RXTX, tim: TIM, rng: RNG, hooks: H) -> Self {
+        LoRaRadio {
+            radio,
+            tim,
+            rng,
+            hooks,
+            clock_drift: 0,
+            wake_up_time: Duration::ZERO,
+            poll_interval: Self::MIN_POLL_INTERVAL,
+            durations: StateDurations::default(),
+            listening: false,
+            err: PhantomData,
+        }
+    }
+
     pub fn as_radio(&self) -> &RXTX {
         &self.radio
     }
@@ -49,169 +108,571 @@ impl<RXTX, TIM, RNG, ERR> LoRaRadio<RXTX, TIM, RNG, ERR> {
     pub fn as_mut_rng(&mut self) -> &mut RNG {
         &mut self.rng
     }
+
+    pub fn as_hooks(&self) -> &H {
+        &self.hooks
+    }
+
+    pub fn as_mut_hooks(&mut self) -> &mut H {
+        &mut self.hooks
+    }
+
+    /// The largest drift of the clock, in parts per million.
+    pub fn clock_drift(&self) -> u32 {
+        self.clock_drift
+    }
+
+    /// Sets the largest drift of the clock, in parts per million. The receive windows are widened
+    /// by the drift over their delay, which matters most for long delays, e.g. an RX1 delay of
+    /// 15 seconds.
+    pub fn set_clock_drift(&mut self, ppm: u32) {
+        self.clock_drift = ppm;
+    }
+
+    /// How long before a receive window the device wakes up.
+    pub fn wake_up_time(&self) -> Duration {
+        self.wake_up_time
+    }
+
+    /// Sets how long before a receive window the device wakes up, to make up for the time the MCU
+    /// and radio take to wake up from sleep.
+    pub fn set_wake_up_time(&mut self, duration: Duration) {
+        self.wake_up_time = duration;
+    }
+
+    /// How long the radio spent in each state, since it was created or last reset.
+    pub fn state_durations(&self) -> &StateDurations {
+        &self.durations
+    }
+
+    pub fn reset_state_durations(&mut self) {
+        self.durations = StateDurations::default();
+    }
+
+    /// Returns the channels and timing of RX1 and RX2 after an uplink at `tx_dr` on `channel`, for
+    /// an RX1 delay of `delay`. The windows are widened by `tx_error`, the largest error in the
+    /// time the transmission is known to have ended.
+    fn response_windows<R: RegionalParameters>(
+        &self,
+        settings: &Settings<R>,
+        tx_dr: usize,
+        dwell_time: bool,
+        delay: Duration,
+        channel: usize,
+        tx_error: Duration,
+    ) -> Result<ResponseWindows, RadioError<ERR>> {
+        let region = settings.region();
+        let [(rx1_dr, rx1_window), (rx2_dr, rx2_window)] = rx_windows(
+            settings,
+            tx_dr,
+            dwell_time,
+            delay,
+            Self::CLOCK_ERROR + tx_error,
+            self.clock_drift,
+        )
+        .ok_or(RadioError::UnsupportedDataRate)?;
+
+        Ok(ResponseWindows {
+            rx1_channel: settings
+                .rx1_channel(rx1_dr, channel)
+                .ok_or(RadioError::UnsupportedDataRate)?,
+            rx1_window,
+            rx1_start: delay.saturating_sub(rx1_window.offset()),
+            rx2_channel: region
+                .rx2_channel(rx2_dr)
+                .ok_or(RadioError::UnsupportedDataRate)?,
+            rx2_window,
+            rx2_start: (delay + NEXT_DELAY).saturating_sub(rx2_window.offset()),
+        })
+    }
+
+    /// How often to poll a radio that does not signal events, while it uses `data_rate`. This is
+    /// once per symbol, which is as precisely as the radio can tell when a message started or
+    /// ended.
+    fn poll_interval(data_rate: &DataRate) -> Duration {
+        data_rate.symbol_time().max(Self::MIN_POLL_INTERVAL)
+    }
+}
+
+impl<RXTX, TIM, RNG: RngCore, ERR, H> LoRaRadio<RXTX, TIM, RNG, ERR, H> {
+    /// Picks a random enabled channel for an uplink at data rate `dr`.
+    fn random_channel<R: RegionalParameters>(
+        &mut self,
+        settings: &Settings<R>,
+        dr: usize,
+    ) -> Result<usize, RadioError<ERR>> {
+        let noise = self.random_u8()? as usize;
+        settings
+            .select_channel(dr, noise)
+            .ok_or(RadioError::NoChannel)
+    }
+
+    /// Picks a random enabled channel to check with listen-before-talk, and returns it with its
+    /// configuration.
+    fn lbt_candidate<R: RegionalParameters>(
+        &mut self,
+        settings: &Settings<R>,
+        dr: usize,
+    ) -> Result<(usize, ChannelConfig), RadioError<ERR>> {
+        let channel = self.random_channel(settings, dr)?;
+        let tx_channel = settings
+            .tx_channel(dr, channel)
+            .ok_or(RadioError::UnsupportedDataRate)?;
+        Ok((channel, tx_channel))
+    }
+
+    fn random_u8(&mut self) -> Result<u8, RadioError<ERR>> {
+        let mut byte = [0];
+        self.rng
+            .try_fill_bytes(&mut byte)
+            .map_err(RadioError::Random)?;
+        Ok(byte[0])
+    }
+
+    pub(crate) fn random_nonce(&mut self) -> Result<u16, RadioError<ERR>> {
+        let mut byte = [0, 0];
+        self.rng
+            .try_fill_bytes(&mut byte)
+            .map_err(RadioError::Random)?;
+        Ok(u16::from_le_bytes(byte))
+    }
 }
 
-impl<RXTX, TIM, RNG, ERR, INFO, CH> LoRaRadio<RXTX, TIM, RNG, ERR>
+impl<RXTX, TIM, RNG, ERR, INFO, CH, H> LoRaRadio<RXTX, TIM, RNG, ERR, H>
 where
     RXTX: Receive<Error = ERR, Info = INFO>,
     RXTX: Transmit<Error = ERR>,
     RXTX: Channel<Channel = CH, Error = ERR>,
     RXTX: Busy<Error = ERR>,
-    TIM: DelayUs<u32>,
+    TIM: DelayUs<u32> + Clock,
     RNG: RngCore,
     ERR: Debug,
     INFO: Into<LoRaInfo>,
-    CH: From<LoRaChannel>,
+    CH: RadioChannel,
+    H: RadioHooks<RXTX>,
 {
     /// The time the radio will have to transmit a message before a timeout occurs.
     const TX_TIMEOUT: Duration = Duration::from_millis(4000);
 
-    /// The time the radio will listen for a message on a channel. This must be long enough for the
-    /// radio to receive a preamble, in which case it will continue listening for the message. It
-    /// must not exceed one second, because the radio must switch to RX2 within that time if it does
-    /// not receive a message on RX1.
-    const RX_TIMEOUT: Duration = Duration::from_millis(800);
-
-    /// How often the radio will check whether a message has been sent or received completely.
-    const INTERVAL: Duration = Duration::from_millis(100);
-
-    /// How much earlier to start listening for a message than `RX1_DELAY` and `RX2_DELAY`.
-    const DELAY_MARGIN: Duration = Duration::from_millis(50);
+    pub fn lorawan_transmit<R: RegionalParameters>(
+        &mut self,
+        tx: &[u8],
+        rx: &mut [u8],
+        tx_dr: usize,
+        dwell_time: bool,
+        settings: &Settings<R>,
+    ) -> Result<Option<(usize, LoRaInfo)>, RadioError<ERR>> {
+        self.lorawan_transmit_delayed(tx, rx, tx_dr, dwell_time, settings.rx_delay(), settings)
+    }
 
-    /// Constructs a new LoRa radio.
-    pub fn new(radio: RXTX, tim: TIM, rng: RNG) -> Self {
-        LoRaRadio {
-            radio,
-            tim,
-            rng,
-            err: PhantomData,
-        }
+    /// Basic LoRaWAN transmit. It transmits `tx`, then waits for a response on RX1, and if it does
+    /// not receive anything, it waits for a response on RX2. The response is stored in `rx`. If no
+    /// response is received, this method returns a timeout error. `dwell_time` indicates whether
+    /// downlinks are limited to a dwell time of 400 ms.
+    pub fn lorawan_transmit_delayed<R: RegionalParameters>(
+        &mut self,
+        tx: &[u8],
+        rx: &mut [u8],
+        tx_dr: usize,
+        dwell_time: bool,
+        delay: Duration,
+        settings: &Settings<R>,
+    ) -> Result<Option<(usize, LoRaInfo)>, RadioError<ERR>> {
+        self.transmit_and_receive(tx, rx, tx_dr, dwell_time, delay, settings, false)
     }
 
-    pub fn lorawan_transmit<R: Region>(
+    /// Class C version of [lorawan_transmit]. Instead of sleeping, the radio listens on RX2 until
+    /// RX1 opens, and after RX1 until the end of the RX2 window. Afterwards, it keeps listening on
+    /// RX2 for [lorawan_listen].
+    ///
+    /// [lorawan_transmit]: LoRaRadio::lorawan_transmit
+    /// [lorawan_listen]: LoRaRadio::lorawan_listen
+    pub fn lorawan_transmit_class_c<R: RegionalParameters>(
         &mut self,
         tx: &[u8],
         rx: &mut [u8],
         tx_dr: usize,
+        dwell_time: bool,
         settings: &Settings<R>,
     ) -> Result<Option<(usize, LoRaInfo)>, RadioError<ERR>> {
-        self.lorawan_transmit_delayed(tx, rx, tx_dr, settings.rx_delay(), settings)
+        let delay = settings.rx_delay();
+        let response = self.transmit_and_receive(tx, rx, tx_dr, dwell_time, delay, settings, true);
+        match (response, self.start_listening(settings)) {
+            // A downlink must not be lost to a failure to keep listening, which lorawan_listen
+            // retries
+            (Ok(Some(downlink)), _) => Ok(Some(downlink)),
+            (Err(error), _) => Err(error),
+            (Ok(None), listening) => listening.map(|()| None),
+        }
     }
 
-    /// Basic LoRaWAN transmit. It transmits `tx`, then waits for a response on RX1, and if it does
-    /// not receive anything, it waits for a response on RX2. The response is stored in `rx`. If no
-    /// response is received, this method returns a timeout error.
-    pub fn lorawan_transmit_delayed<R: Region>(
+    /// Listens on RX2 for a class C downlink for up to `timeout`, and stores it in `rx`. The radio
+    /// keeps listening after this returns, so that downlinks arriving in between calls are not
+    /// lost.
+    pub fn lorawan_listen<R: RegionalParameters>(
+        &mut self,
+        rx: &mut [u8],
+        timeout: Duration,
+        settings: &Settings<R>,
+    ) -> Result<Option<(usize, LoRaInfo)>, RadioError<ERR>> {
+        if !self.listening {
+            self.start_listening(settings)?;
+        }
+
+        let response = match self.wait_for_message(rx, timeout) {
+            Ok(response) => Some(response),
+            Err(RadioError::Timeout) => None,
+            Err(error) => return Err(error),
+        };
+        if response.is_some() {
+            self.radio.start_receive()?;
+        }
+        Ok(response)
+    }
+
+    /// Listens on `channel` for up to `timeout`, and stores any message in `rx`.
+    pub fn lorawan_receive(
+        &mut self,
+        rx: &mut [u8],
+        channel: ChannelConfig,
+        timeout: Duration,
+    ) -> Result<Option<(usize, LoRaInfo)>, RadioError<ERR>> {
+        self.set_channel(channel)?;
+        match self.receive_raw(rx, timeout) {
+            Ok(response) => Ok(Some(response)),
+            Err(RadioError::Timeout) => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Sleeps until a downlink at `data_rate` is about to start at `start`, and listens for it on
+    /// `channel`. The window is widened for the clock drift over `since_sync`, the time since the
+    /// clock was last synchronized with the network. This is used for class B beacons and ping
+    /// slots.
+    pub fn lorawan_receive_at(
+        &mut self,
+        rx: &mut [u8],
+        channel: ChannelConfig,
+        data_rate: &DataRate,
+        start: Duration,
+        since_sync: Duration,
+    ) -> Result<Option<(usize, LoRaInfo)>, RadioError<ERR>> {
+        let drift = since_sync.as_micros() as u64 * self.clock_drift as u64 / 1_000_000;
+        let clock_error = Self::CLOCK_ERROR + Duration::from_micros(drift);
+        let window = RxWindow::for_channel(&channel, data_rate, clock_error);
+
+        self.set_channel(channel)?;
+        self.sleep_until(start.saturating_sub(window.offset()))?;
+        match self.receive_raw(rx, window.timeout()) {
+            Ok(response) => Ok(Some(response)),
+            Err(RadioError::Timeout) => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Transmits `tx`, and receives a response in RX1 or RX2. In class C, the radio listens on RX2
+    /// whenever it is not transmitting or in RX1.
+    #[allow(clippy::too_many_arguments)]
+    fn transmit_and_receive<R: RegionalParameters>(
         &mut self,
         tx: &[u8],
         rx: &mut [u8],
         tx_dr: usize,
+        dwell_time: bool,
         delay: Duration,
         settings: &Settings<R>,
+        class_c: bool,
     ) -> Result<Option<(usize, LoRaInfo)>, RadioError<ERR>> {
-        let rx1_dr = tx_dr + settings.rx1_dr_offset();
-        let rx2_dr = settings.rx2_dr();
+        let region = settings.region();
+        let channel = self.select_channel(settings, tx_dr)?;
+        // Without events, the end of the transmission is only known to within a poll interval
+        let tx_error = region
+            .data_rate(tx_dr)
+            .map_or(Duration::ZERO, Self::poll_interval);
+        let windows =
+            self.response_windows(settings, tx_dr, dwell_time, delay, channel, tx_error)?;
 
         #[cfg(feature = "defmt")]
         defmt::trace!("transmitting LoRaWAN packet");
-        let noise = self.random_u8()? as usize;
-        self.radio
-            .set_channel(&R::get_data_rate(tx_dr)?.tx(noise).into())?;
+        let tx_channel = settings
+            .tx_channel(tx_dr, channel)
+            .ok_or(RadioError::UnsupportedDataRate)?;
+        self.set_channel(tx_channel)?;
+        self.hooks.set_power(&mut self.radio, settings.max_eirp())?;
         self.transmit_raw(tx)?;
+        let tx_done = self.tim.now();
 
-        #[cfg(feature = "defmt")]
-        defmt::trace!("waiting for RX1 window");
-        self.radio
-            .set_channel(&R::get_data_rate(rx1_dr)?.rx1(noise).into())?;
-        self.tim
-            .delay_us((delay - Self::DELAY_MARGIN).as_micros() as u32);
+        for listen in windows.listens(tx_done, class_c) {
+            self.set_channel(listen.channel.clone())?;
+            if let Some(start) = listen.start {
+                self.sleep_until(start)?;
+            }
+
+            #[cfg(feature = "defmt")]
+            defmt::trace!("receiving on {}", listen.name);
+            match self.receive_raw(rx, listen.timeout(self.tim.now())) {
+                Ok(response) => {
+                    #[cfg(feature = "defmt")]
+                    defmt::trace!("response received");
+                    return Ok(Some(response));
+                }
+                Err(RadioError::Timeout) => {}
+                Err(error) => return Err(error),
+            }
+        }
 
         #[cfg(feature = "defmt")]
-        defmt::trace!("receiving on RX1");
-        match self.receive_raw(rx) {
-            Ok((n, info)) => {
-                #[cfg(feature = "defmt")]
-                defmt::trace!("response received");
-                Ok(Some((n, info)))
+        defmt::trace!("no response");
+        Ok(None)
+    }
+
+    /// Starts listening on RX2 for class C downlinks.
+    fn start_listening<R: RegionalParameters>(
+        &mut self,
+        settings: &Settings<R>,
+    ) -> Result<(), RadioError<ERR>> {
+        let rx2_channel = settings
+            .region()
+            .rx2_channel(settings.rx2_dr())
+            .ok_or(RadioError::UnsupportedDataRate)?;
+        self.set_channel(rx2_channel)?;
+        self.radio.start_receive()?;
+        self.listening = true;
+        Ok(())
+    }
+
+    /// Picks a random enabled channel for an uplink at data rate `dr`. If the region requires
+    /// listen-before-talk, this keeps picking channels until it finds one that is free.
+    fn select_channel<R: RegionalParameters>(
+        &mut self,
+        settings: &Settings<R>,
+        dr: usize,
+    ) -> Result<usize, RadioError<ERR>> {
+        let lbt = match settings.region().listen_before_talk() {
+            None => return self.random_channel(settings, dr),
+            Some(lbt) => lbt,
+        };
+
+        for _ in 0..Self::LBT_ATTEMPTS {
+            let (channel, tx_channel) = self.lbt_candidate(settings, dr)?;
+            if self.is_channel_free(tx_channel, lbt)? {
+                return Ok(channel);
             }
-            Err(RadioError::Timeout) => {
-                #[cfg(feature = "defmt")]
-                defmt::trace!("nothing received, waiting for RX2 window");
-                self.radio
-                    .set_channel(&R::get_data_rate(rx2_dr)?.rx2().into())?;
-                self.tim
-                    .delay_us((NEXT_DELAY - Self::RX_TIMEOUT).as_micros() as u32);
-
-                #[cfg(feature = "defmt")]
-                defmt::trace!("receiving on RX2");
-                match self.receive_raw(rx) {
-                    Ok((n, info)) => {
-                        #[cfg(feature = "defmt")]
-                        defmt::trace!("response received");
-                        Ok(Some((n, info)))
-                    }
-                    Err(RadioError::Timeout) => {
-                        #[cfg(feature = "defmt")]
-                        defmt::trace!("no response");
-                        Ok(None)
-                    }
-                    Err(error) => Err(error),
-                }
+
+            #[cfg(feature = "defmt")]
+            defmt::trace!("channel {} is busy", channel);
+        }
+
+        Err(RadioError::ChannelBusy)
+    }
+
+    /// Listens on `channel` for the duration required by `lbt`, and checks whether the RSSI stays
+    /// below the threshold.
+    fn is_channel_free(
+        &mut self,
+        channel: ChannelConfig,
+        lbt: &ListenBeforeTalk,
+    ) -> Result<bool, RadioError<ERR>> {
+        self.set_channel(channel)?;
+        self.radio.start_receive()?;
+
+        let start = self.tim.now();
+        let mut time = Duration::ZERO;
+        let free = loop {
+            if time >= lbt.duration() {
+                break true;
             }
-            Err(error) => Err(error),
+            self.tim.delay_us(Self::LBT_INTERVAL.as_micros() as u32);
+            let rssi = self
+                .hooks
+                .rssi(&mut self.radio)
+                .ok_or(RadioError::UnsupportedListenBeforeTalk)??;
+            if rssi >= lbt.threshold() {
+                break false;
+            }
+            time += Self::LBT_INTERVAL;
+        };
+
+        self.durations.add_receiving(self.tim.now() - start);
+        Ok(free)
+    }
+
+    /// Configures the radio for `channel`, if the radio supports its modulation.
+    fn set_channel(&mut self, channel: ChannelConfig) -> Result<(), RadioError<ERR>> {
+        self.poll_interval = Self::poll_interval(&channel.data_rate());
+        let channel = match channel {
+            ChannelConfig::LoRa(channel) => Some(channel.into()),
+            ChannelConfig::Fsk(channel) => self.hooks.fsk_channel(channel),
+            ChannelConfig::LrFhss(channel) => self.hooks.lr_fhss_channel(channel),
+            ChannelConfig::LongInterleaving(channel) => {
+                self.hooks.long_interleaving_channel(channel)
+            }
+            ChannelConfig::Beacon(channel) => self.hooks.beacon_channel(channel),
         }
+        .ok_or(RadioError::UnsupportedDataRate)?;
+        self.radio.set_channel(&channel)?;
+        self.listening = false;
+        Ok(())
+    }
+
+    /// Puts the radio and MCU to sleep until the clock reaches `instant`, minus the wake-up time,
+    /// or returns immediately if it has passed. The radio must already be configured for the
+    /// receive window that follows.
+    fn sleep_until(&mut self, instant: Duration) -> Result<(), RadioError<ERR>> {
+        let instant = instant.saturating_sub(self.wake_up_time);
+        let now = self.tim.now();
+        if instant > now {
+            self.hooks.sleep_radio(&mut self.radio)?;
+            self.listening = false;
+            if !self.hooks.sleep(instant - now) {
+                self.tim.delay_us((instant - now).as_micros() as u32);
+            }
+            self.durations.add_sleeping(self.tim.now() - now);
+        }
+        Ok(())
+    }
+
+    /// Waits for the radio to signal an event, for up to `timeout`. If the radio does not signal
+    /// events through [RadioHooks::wait_for_event], this waits for the poll interval of the
+    /// current channel instead, after which the radio must be polled.
+    fn wait_for_event(&mut self, timeout: Duration) -> Option<RadioEvent> {
+        let event = self.hooks.wait_for_event(&mut self.radio, timeout);
+        if event.is_none() {
+            self.tim.delay_us(self.poll_interval.as_micros() as u32);
+        }
+        event
     }
 
     /// Attempts to transmit a message.
     fn transmit_raw(&mut self, data: &[u8]) -> Result<(), RadioError<ERR>> {
         self.radio.start_transmit(data)?;
 
-        for _ in 0..Self::TX_TIMEOUT.as_millis() / Self::INTERVAL.as_millis() {
-            self.tim.delay_us(Self::INTERVAL.as_micros() as u32);
+        let start = self.tim.now();
+        loop {
+            let elapsed = self.tim.now() - start;
+            if elapsed >= Self::TX_TIMEOUT {
+                self.durations.add_transmitting(elapsed);
+                return Err(RadioError::Timeout);
+            }
 
+            self.wait_for_event(Self::TX_TIMEOUT - elapsed);
             if self.radio.check_transmit()? {
+                self.durations.add_transmitting(self.tim.now() - start);
                 return Ok(());
             }
         }
-
-        Err(RadioError::Timeout)
     }
 
-    /// Attempts to receive a message. This returns within one second if no message is being
-    /// received, giving enough time to switch to RX2 if necessary.
-    fn receive_raw(&mut self, buf: &mut [u8]) -> Result<(usize, LoRaInfo), RadioError<ERR>> {
+    /// Attempts to receive a message. This returns a timeout error if the radio has not started
+    /// receiving a message within `timeout`.
+    fn receive_raw(
+        &mut self,
+        buf: &mut [u8],
+        timeout: Duration,
+    ) -> Result<(usize, LoRaInfo), RadioError<ERR>> {
         self.radio.start_receive()?;
+        self.wait_for_message(buf, timeout)
+    }
 
-        let mut time = 0;
-        loop {
-            self.tim.delay_us(Self::INTERVAL.as_micros() as u32);
+    /// Waits for the radio, which must already be receiving, to receive a message. This returns a
+    /// timeout error if the radio has not started receiving a message within `timeout`.
+    fn wait_for_message(
+        &mut self,
+        buf: &mut [u8],
+        timeout: Duration,
+    ) -> Result<(usize, LoRaInfo), RadioError<ERR>> {
+        let start = self.tim.now();
+        let result = loop {
+            // Once the timeout has passed, the radio is receiving a message, so wait for it to
+            // finish instead of for the timeout
+            let elapsed = self.tim.now() - start;
+            let remaining = match timeout.checked_sub(elapsed) {
+                Some(remaining) if !remaining.is_zero() => remaining,
+                _ => Self::TX_TIMEOUT,
+            };
+            if self.wait_for_event(remaining) == Some(RadioEvent::Timeout) {
+                break Err(RadioError::Timeout);
+            }
 
             if self.radio.check_receive(false)? {
                 let (n, i) = self.radio.get_received(buf)?;
-                return Ok((n, i.into()));
+                break Ok((n, i.into()));
             }
 
-            time += Self::INTERVAL.as_micros();
-            if time >= Self::RX_TIMEOUT.as_micros() && !self.radio.is_busy()? {
-                return Err(RadioError::Timeout);
+            if self.tim.now() - start >= timeout && !self.radio.is_busy()? {
+                break Err(RadioError::Timeout);
             }
-        }
+        };
+
+        self.durations.add_receiving(self.tim.now() - start);
+        result
     }
+}
 
-    fn random_u8(&mut self) -> Result<u8, RadioError<ERR>> {
-        let mut byte = [0];
-        self.rng
-            .try_fill_bytes(&mut byte)
-            .map_err(RadioError::Random)?;
-        Ok(byte[0])
+/// The channels and timing of the receive windows after an uplink. The start of each window is
+/// relative to the end of the uplink.
+struct ResponseWindows {
+    rx1_channel: ChannelConfig,
+    rx1_window: RxWindow,
+    rx1_start: Duration,
+    rx2_channel: ChannelConfig,
+    rx2_window: RxWindow,
+    rx2_start: Duration,
+}
+
+impl ResponseWindows {
+    /// Returns the periods to listen for a response after an uplink that ended at `tx_done`, in
+    /// order. In class C, the radio listens on RX2 until RX1 opens, and after RX1 until the end of
+    /// the RX2 window, instead of sleeping.
+    fn listens(self, tx_done: Duration, class_c: bool) -> impl Iterator<Item = Listen> {
+        let rx1_start = tx_done + self.rx1_start;
+        let rx2_start = tx_done + self.rx2_start;
+
+        let before_rx1 = class_c.then(|| Listen {
+            name: "RX2 until RX1",
+            channel: self.rx2_channel.clone(),
+            start: None,
+            until: Some(rx1_start),
+            timeout: Duration::ZERO,
+        });
+        let rx1 = Listen {
+            name: "RX1",
+            channel: self.rx1_channel,
+            start: (!class_c).then_some(rx1_start),
+            until: None,
+            timeout: self.rx1_window.timeout(),
+        };
+        // In class C, RX2 opens right away and lasts until the end of the regular RX2 window
+        let rx2 = Listen {
+            name: "RX2",
+            channel: self.rx2_channel,
+            start: (!class_c).then_some(rx2_start),
+            until: class_c.then_some(rx2_start),
+            timeout: self.rx2_window.timeout(),
+        };
+
+        [before_rx1, Some(rx1), Some(rx2)].into_iter().flatten()
     }
+}
 
-    pub(crate) fn random_nonce(&mut self) -> Result<u16, RadioError<ERR>> {
-        let mut byte = [0, 0];
-        self.rng
-            .try_fill_bytes(&mut byte)
-            .map_err(RadioError::Random)?;
-        Ok(u16::from_le_bytes(byte))
+/// A period of listening for the response to an uplink, shared by the blocking and async radios.
+struct Listen {
+    #[cfg_attr(not(feature = "defmt"), allow(dead_code))]
+    name: &'static str,
+    channel: ChannelConfig,
+    /// When to start listening, or `None` to start right away.
+    start: Option<Duration>,
+    /// Until when to listen before the timeout starts, if at all.
+    until: Option<Duration>,
+    timeout: Duration,
+}
+
+impl Listen {
+    /// How long to listen, when starting at `now`.
+    fn timeout(&self, now: Duration) -> Duration {
+        self.until
+            .map_or(Duration::ZERO, |until| until.saturating_sub(now))
+            + self.timeout
     }
 }
 
@@ -222,6 +683,12 @@ pub enum RadioError<ERR> {
     /// Failed to generate a random number.
     Random(rand_core::Error),
     UnsupportedDataRate,
+    /// None of the enabled channels supports the requested data rate.
+    NoChannel,
+    /// Listen-before-talk did not find a free channel.
+    ChannelBusy,
+    /// The region requires listen-before-talk, but the radio can not measure the RSSI.
+    UnsupportedListenBeforeTalk,
     Timeout,
 }
 
@@ -1,63 +1,251 @@
-use core::marker::PhantomData;
+use core::time::Duration;
 
 use radio::modulation::lora::{CodingRate, LoRaChannel, SpreadingFactor};
 
-use crate::radio::Region;
-
 pub type Hz = u32;
 
-#[derive(Debug, PartialEq)]
-pub struct DataRate<R> {
-    spreading_factor: SpreadingFactor,
-    frequency: Hz,
-    _region: PhantomData<R>,
+/// The number of preamble symbols of a class B beacon.
+const BEACON_PREAMBLE_SYMBOLS: u16 = 10;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum DataRate {
+    LoRa {
+        spreading_factor: SpreadingFactor,
+        bandwidth: Hz,
+    },
+    /// Gaussian frequency-shift keying, with a frequency deviation of half the bitrate.
+    Fsk { bitrate: u32 },
+    /// Long Range Frequency Hopping Spread Spectrum, which hops within `bandwidth` around the
+    /// channel frequency, on a grid of `grid` steps. It is only used for uplinks.
+    LrFhss {
+        coding_rate: LrFhssCodingRate,
+        bandwidth: Hz,
+        grid: Hz,
+    },
 }
 
-impl<R> DataRate<R> {
-    pub(in crate::radio) const fn new(spreading_factor: SpreadingFactor, frequency: Hz) -> Self {
-        DataRate {
+impl DataRate {
+    /// Creates a LoRa data rate.
+    pub const fn new(spreading_factor: SpreadingFactor, bandwidth: Hz) -> Self {
+        DataRate::LoRa {
             spreading_factor,
-            frequency,
-            _region: PhantomData,
+            bandwidth,
         }
     }
-}
 
-impl<R: Region> DataRate<R> {
-    pub fn tx(&self, noise: usize) -> LoRaChannel {
-        LoRaChannel {
-            freq_khz: R::TX_FREQUENCIES[noise % R::TX_FREQUENCIES.len()] / 1000,
-            bw_khz: (self.frequency / 1000) as u16,
-            sf: self.spreading_factor,
-            cr: CodingRate::Cr4_5,
+    /// Creates an FSK data rate with a bitrate of `bitrate` bits per second.
+    pub const fn fsk(bitrate: u32) -> Self {
+        DataRate::Fsk { bitrate }
+    }
+
+    /// Creates an LR-FHSS data rate.
+    pub const fn lr_fhss(coding_rate: LrFhssCodingRate, bandwidth: Hz, grid: Hz) -> Self {
+        DataRate::LrFhss {
+            coding_rate,
+            bandwidth,
+            grid,
+        }
+    }
+
+    /// Whether the data rate may only be used for uplinks.
+    pub fn is_uplink_only(&self) -> bool {
+        matches!(self, DataRate::LrFhss { .. })
+    }
+
+    /// The duration of a symbol. For FSK, this is the duration of a byte, as FSK preambles are
+    /// counted in bytes.
+    pub fn symbol_time(&self) -> Duration {
+        match *self {
+            DataRate::LoRa {
+                spreading_factor,
+                bandwidth,
+            } => {
+                let sf = spreading_factor_bits(spreading_factor);
+                Duration::from_micros((1_000_000 << sf) / bandwidth as u64)
+            }
+            DataRate::Fsk { bitrate } => Duration::from_micros(8_000_000 / bitrate as u64),
+            // LR-FHSS transmits 488.28125 symbols per second
+            DataRate::LrFhss { .. } => Duration::from_micros(2048),
         }
     }
 
-    pub fn rx1(&self, noise: usize) -> LoRaChannel {
-        LoRaChannel {
-            freq_khz: R::RX1_FREQUENCIES[noise % R::RX1_FREQUENCIES.len()] / 1000,
-            bw_khz: (self.frequency / 1000) as u16,
-            sf: self.spreading_factor,
-            cr: CodingRate::Cr4_5,
+    /// The number of preamble symbols of a downlink, in units of [symbol_time]: eight LoRa
+    /// symbols, or five bytes for FSK. LR-FHSS is only used for uplinks, so its preamble is never
+    /// received and the LoRa length is returned.
+    ///
+    /// [symbol_time]: DataRate::symbol_time
+    pub fn preamble_symbols(&self) -> u32 {
+        match self {
+            DataRate::Fsk { .. } => 5,
+            DataRate::LoRa { .. } | DataRate::LrFhss { .. } => 8,
         }
     }
 
-    pub fn rx2(&self) -> LoRaChannel {
-        LoRaChannel {
-            freq_khz: R::RX2_FREQUENCY / 1000,
-            bw_khz: (self.frequency / 1000) as u16,
-            sf: self.spreading_factor,
-            cr: CodingRate::Cr4_5,
+    /// Returns the channel to receive a class B beacon of `size` bytes on at `frequency`. Beacons
+    /// are sent with a preamble of ten symbols, an implicit header, no CRC and a coding rate of
+    /// 4/5. Returns `None` for data rates other than LoRa.
+    pub fn beacon_channel(&self, frequency: Hz, size: usize) -> Option<ChannelConfig> {
+        match self.channel(frequency, CodingRate::Cr4_5) {
+            ChannelConfig::LoRa(lora) => Some(ChannelConfig::Beacon(BeaconChannel {
+                lora,
+                preamble_symbols: BEACON_PREAMBLE_SYMBOLS,
+                length: size as u8,
+            })),
+            _ => None,
+        }
+    }
+
+    /// The time it takes to transmit a class B beacon of `size` bytes, with the parameters of
+    /// [beacon_channel]. Returns `None` for data rates other than LoRa.
+    ///
+    /// [beacon_channel]: DataRate::beacon_channel
+    pub fn beacon_air_time(&self, size: usize) -> Option<Duration> {
+        let sf = match *self {
+            DataRate::LoRa {
+                spreading_factor, ..
+            } => spreading_factor_bits(spreading_factor) as i64,
+            _ => return None,
+        };
+        let symbol_time = self.symbol_time();
+        // Low data rate optimization is used when symbols last at least 16 ms
+        let de = (symbol_time >= Duration::from_millis(16)) as i64;
+
+        let bits = 8 * size as i64 - 4 * sf + 28 - 20;
+        let blocks = (bits + 4 * (sf - 2 * de) - 1)
+            .div_euclid(4 * (sf - 2 * de))
+            .max(0);
+        // The preamble takes 4.25 symbols more than its length
+        let preamble = BEACON_PREAMBLE_SYMBOLS as u32;
+        let quarter_symbols = 4 * (preamble + 8 + blocks as u32 * 5) + 17;
+
+        Some(symbol_time * quarter_symbols / 4)
+    }
+
+    /// Returns the channel for this data rate at `frequency`. `coding_rate` only applies to LoRa.
+    pub fn channel(&self, frequency: Hz, coding_rate: CodingRate) -> ChannelConfig {
+        match *self {
+            DataRate::LoRa {
+                spreading_factor,
+                bandwidth,
+            } => ChannelConfig::LoRa(LoRaChannel {
+                freq_khz: frequency / 1000,
+                bw_khz: (bandwidth / 1000) as u16,
+                sf: spreading_factor,
+                cr: coding_rate,
+            }),
+            DataRate::Fsk { bitrate } => ChannelConfig::Fsk(FskChannel {
+                freq_khz: frequency / 1000,
+                bitrate_bps: bitrate,
+                deviation_hz: bitrate / 2,
+            }),
+            DataRate::LrFhss {
+                coding_rate,
+                bandwidth,
+                grid,
+            } => ChannelConfig::LrFhss(LrFhssChannel {
+                freq_khz: frequency / 1000,
+                coding_rate,
+                bandwidth_hz: bandwidth,
+                grid_hz: grid,
+            }),
         }
     }
 }
 
-impl<R> Clone for DataRate<R> {
-    fn clone(&self) -> Self {
-        DataRate {
-            spreading_factor: self.spreading_factor,
-            frequency: self.frequency,
-            _region: PhantomData,
+/// The parameters of an FSK channel, with a Gaussian filter with BT = 0.5.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FskChannel {
+    pub freq_khz: u32,
+    pub bitrate_bps: u32,
+    pub deviation_hz: u32,
+}
+
+/// The coding rate of an LR-FHSS data rate.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum LrFhssCodingRate {
+    Cr1_3,
+    Cr2_3,
+}
+
+/// The hopping parameters of an LR-FHSS transmission, centered on `freq_khz`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LrFhssChannel {
+    pub freq_khz: u32,
+    pub coding_rate: LrFhssCodingRate,
+    /// The occupied channel width the radio hops within.
+    pub bandwidth_hz: u32,
+    /// The spacing of the frequencies the radio hops between.
+    pub grid_hz: u32,
+}
+
+/// The reception parameters of a class B beacon, which unlike other downlinks has an implicit
+/// header of `length` bytes, no CRC, and a preamble of `preamble_symbols` symbols.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BeaconChannel {
+    pub lora: LoRaChannel,
+    pub preamble_symbols: u16,
+    pub length: u8,
+}
+
+/// The channel to configure the radio with, for any modulation.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ChannelConfig {
+    LoRa(LoRaChannel),
+    /// A LoRa channel with long interleaving, which [LoRaChannel] can not describe. Its coding
+    /// rate is 4/8, i.e. CR4/8LI.
+    LongInterleaving(LoRaChannel),
+    Fsk(FskChannel),
+    LrFhss(LrFhssChannel),
+    Beacon(BeaconChannel),
+}
+
+impl ChannelConfig {
+    /// The data rate of the channel.
+    pub fn data_rate(&self) -> DataRate {
+        match self {
+            ChannelConfig::LoRa(channel)
+            | ChannelConfig::LongInterleaving(channel)
+            | ChannelConfig::Beacon(BeaconChannel { lora: channel, .. }) => {
+                DataRate::new(channel.sf, lora_bandwidth(channel.bw_khz))
+            }
+            ChannelConfig::Fsk(channel) => DataRate::fsk(channel.bitrate_bps),
+            ChannelConfig::LrFhss(channel) => {
+                DataRate::lr_fhss(channel.coding_rate, channel.bandwidth_hz, channel.grid_hz)
+            }
         }
     }
 }
+
+/// The channel type of a radio, which is implemented for every type that LoRa channels convert
+/// into. Radios that also support FSK or LR-FHSS convert those channels through [RadioHooks].
+///
+/// [RadioHooks]: crate::radio::RadioHooks
+pub trait RadioChannel: From<LoRaChannel> {}
+
+impl<T: From<LoRaChannel>> RadioChannel for T {}
+
+/// Returns the bandwidth of a LoRa channel of `bw_khz`. The bandwidths of LoRa at 2.4 GHz are not
+/// a whole number of kHz, and are rounded down in [LoRaChannel].
+fn lora_bandwidth(bw_khz: u16) -> Hz {
+    match bw_khz {
+        203 => 203_125,
+        406 => 406_250,
+        812 => 812_500,
+        bw_khz => bw_khz as Hz * 1000,
+    }
+}
+
+/// The number of bits per LoRa symbol.
+fn spreading_factor_bits(spreading_factor: SpreadingFactor) -> u32 {
+    match spreading_factor {
+        SpreadingFactor::Sf5 => 5,
+        SpreadingFactor::Sf6 => 6,
+        SpreadingFactor::Sf7 => 7,
+        SpreadingFactor::Sf8 => 8,
+        SpreadingFactor::Sf9 => 9,
+        SpreadingFactor::Sf10 => 10,
+        SpreadingFactor::Sf11 => 11,
+        SpreadingFactor::Sf12 => 12,
+    }
+}
@@ -0,0 +1,226 @@
+use core::time::Duration;
+
+use radio::modulation::lora::SpreadingFactor;
+
+use crate::radio::{
+    BeaconParameters, CfListType, DataRate, DutyCycleBand, Hz, ListenBeforeTalk, Region,
+};
+
+/// The AS923 regions, which only differ in how far their channels are offset from 923 MHz, and
+/// whether listen-before-talk is required. Use one of the aliases, or their constants as values,
+/// instead of this type directly.
+///
+/// Uplinks and downlinks are limited to a dwell time of 400 ms by default, which makes DR0 and
+/// DR1 unavailable. The network can lift this limit with a TxParamSetupReq.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AS923<const OFFSET: i32, const LBT: bool>;
+
+/// AS923-1, used in e.g. Singapore and Thailand.
+pub type AS923_1 = AS923<0, false>;
+/// AS923-1 as a value, written like the other regions, e.g. `Settings::default_for(AS923_1)`.
+pub const AS923_1: AS923_1 = AS923;
+
+/// AS923-1 with the listen-before-talk requirements of Japan.
+pub type AS923_1JP = AS923<0, true>;
+/// AS923-1 for Japan as a value.
+pub const AS923_1JP: AS923_1JP = AS923;
+
+/// AS923-2, with channels 1.8 MHz below AS923-1.
+pub type AS923_2 = AS923<-1_800_000, false>;
+/// AS923-2 as a value.
+pub const AS923_2: AS923_2 = AS923;
+
+/// AS923-3, with channels 6.6 MHz below AS923-1.
+pub type AS923_3 = AS923<-6_600_000, false>;
+/// AS923-3 as a value.
+pub const AS923_3: AS923_3 = AS923;
+
+/// AS923-4, with channels 5.9 MHz below AS923-1.
+pub type AS923_4 = AS923<-5_900_000, false>;
+/// AS923-4 as a value.
+pub const AS923_4: AS923_4 = AS923;
+
+impl<const OFFSET: i32, const LBT: bool> AS923<OFFSET, LBT> {
+    const fn offset(frequency: Hz) -> Hz {
+        (frequency as i32 + OFFSET) as Hz
+    }
+}
+
+impl<const OFFSET: i32, const LBT: bool> Region for AS923<OFFSET, LBT> {
+    const JOIN_FREQUENCIES: &'static [Hz] = &[Self::offset(923_200_000), Self::offset(923_400_000)];
+
+    const TX_FREQUENCIES: &'static [Hz] = Self::JOIN_FREQUENCIES;
+
+    const RX1_FREQUENCIES: &'static [Hz] = Self::TX_FREQUENCIES;
+
+    const RX2_FREQUENCY: Hz = Self::offset(923_200_000);
+
+    const RX2_DATA_RATE: usize = 2;
+
+    const DATA_RATES: &'static [Option<DataRate>] = &[
+        Some(DataRate::new(SpreadingFactor::Sf12, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf11, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf10, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf9, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf8, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf7, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf7, 250_000)),
+        Some(DataRate::fsk(50_000)),
+    ];
+
+    const MAX_TX_DATA_RATE: usize = 7;
+
+    const MAX_EIRP: i8 = 16;
+
+    const TX_POWERS: &'static [u8] = &[0, 2, 4, 6, 8, 10, 12, 14];
+
+    const MAX_PAYLOAD_SIZES: &'static [u8] = &[59, 59, 123, 123, 250, 250, 250, 250];
+
+    const MAX_PAYLOAD_SIZES_DWELL: &'static [u8] = &[0, 0, 19, 61, 133, 250, 250, 250];
+
+    const UPLINK_DWELL_TIME: bool = true;
+
+    const DOWNLINK_DWELL_TIME: bool = true;
+
+    const TX_PARAM_SETUP: bool = true;
+
+    const JOIN_DATA_RATE: usize = 2;
+
+    // Offsets 6 and 7 raise the data rate by one and two respectively, but RX1 never uses a data
+    // rate above DR5.
+    const RX1_DR_OFFSETS: &'static [&'static [usize]] = &[
+        &[0, 0, 0, 0, 0, 0, 1, 2],
+        &[1, 0, 0, 0, 0, 0, 2, 3],
+        &[2, 1, 0, 0, 0, 0, 3, 4],
+        &[3, 2, 1, 0, 0, 0, 4, 5],
+        &[4, 3, 2, 1, 0, 0, 5, 5],
+        &[5, 4, 3, 2, 1, 0, 5, 5],
+        &[5, 5, 4, 3, 2, 1, 5, 5],
+        &[5, 5, 5, 4, 3, 2, 5, 5],
+    ];
+
+    const BEACON: BeaconParameters<'static> = BeaconParameters::new(
+        3,
+        &[Self::offset(923_400_000)],
+        &[Self::offset(923_400_000)],
+        2,
+        0,
+    );
+
+    const DUTY_CYCLE_BANDS: &'static [DutyCycleBand] = &[];
+
+    const CF_LIST_TYPE: CfListType = CfListType::Frequencies;
+
+    const LISTEN_BEFORE_TALK: Option<ListenBeforeTalk> = if LBT {
+        Some(ListenBeforeTalk::new(-80, Duration::from_millis(5)))
+    } else {
+        None
+    };
+
+    /// While the downlink dwell time applies, RX1 never uses a data rate below DR2.
+    fn rx1_data_rate(tx_dr: usize, rx1_dr_offset: usize, dwell_time: bool) -> Option<usize> {
+        let min_dr = if dwell_time { 2 } else { 0 };
+        Self::RX1_DR_OFFSETS
+            .get(tx_dr)
+            .and_then(|offsets| offsets.get(rx1_dr_offset))
+            .map(|&dr| dr.max(min_dr))
+    }
+
+    /// The default channels only support DR0 to DR5. LoRa at 250 kHz and FSK require channels
+    /// that the network adds.
+    fn supports_data_rate(_channel: usize, dr: usize) -> bool {
+        dr <= 5
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::radio::region::tests::{assert_default_channels_up_to_dr5, assert_rx1_data_rates};
+    use crate::radio::RegionalParameters;
+
+    #[test]
+    fn offsets_channels_from_923_mhz() {
+        let regions: [(&dyn RegionalParameters, Hz); 5] = [
+            (&AS923_1, 923_200_000),
+            (&AS923_1JP, 923_200_000),
+            (&AS923_2, 921_400_000),
+            (&AS923_3, 916_600_000),
+            (&AS923_4, 917_300_000),
+        ];
+        for (region, first) in regions {
+            assert_eq!(region.channel_count(), 2);
+            assert_eq!(region.tx_frequency(0), first);
+            assert_eq!(region.tx_frequency(1), first + 200_000);
+            assert_eq!(region.rx1_frequency(1), first + 200_000);
+            assert_eq!((region.rx2_frequency(), region.rx2_data_rate()), (first, 2));
+            assert_eq!(region.beacon().frequencies(), &[first + 200_000]);
+        }
+    }
+
+    #[test]
+    fn limits_default_channels_to_dr5() {
+        assert_default_channels_up_to_dr5(&AS923_2, &[6, 7]);
+    }
+
+    #[test]
+    fn requires_listen_before_talk_in_japan() {
+        let lbt = AS923_1JP.listen_before_talk().unwrap();
+        assert_eq!(lbt.threshold(), -80);
+        assert_eq!(lbt.duration(), Duration::from_millis(5));
+        assert_eq!(AS923_1.listen_before_talk(), None);
+    }
+
+    #[test]
+    fn limits_payload_sizes() {
+        let region = AS923_1;
+        let sizes = [
+            (0, 59, 0),
+            (1, 59, 0),
+            (2, 123, 19),
+            (3, 123, 61),
+            (4, 250, 133),
+            (7, 250, 250),
+        ];
+        for (dr, m, m_dwell) in sizes {
+            assert_eq!(region.max_mac_payload_size(dr, false), m, "DR{}", dr);
+            assert_eq!(region.max_mac_payload_size(dr, true), m_dwell, "DR{}", dr);
+        }
+    }
+
+    #[test]
+    fn caps_rx1_data_rate() {
+        // RX1 DR = min(5, max(min DR, DR - offset)), where offsets 6 and 7 count as -1 and -2
+        let region = AS923_1;
+        assert_rx1_data_rates(
+            &region,
+            false,
+            &[
+                (0, 0, Some(0)),
+                (0, 7, Some(2)),
+                (2, 5, Some(0)),
+                (3, 6, Some(4)),
+                (4, 7, Some(5)),
+                (6, 0, Some(5)),
+                (6, 2, Some(4)),
+                (7, 0, Some(5)),
+                (7, 2, Some(5)),
+                (7, 3, Some(4)),
+                (7, 5, Some(2)),
+                (7, 7, Some(5)),
+                (2, 8, None),
+            ],
+        );
+        assert_rx1_data_rates(
+            &region,
+            true,
+            &[
+                (0, 0, Some(2)),
+                (2, 5, Some(2)),
+                (5, 2, Some(3)),
+                (7, 5, Some(2)),
+            ],
+        );
+    }
+}
@@ -0,0 +1,167 @@
+use radio::modulation::lora::SpreadingFactor;
+
+use crate::radio::region::{channels, fixed_uplink_channels, FIXED_SUB_BANDS};
+use crate::radio::{
+    BeaconParameters, CfListType, ChannelMask, DataRate, DutyCycleBand, Hz, Region,
+};
+
+/// The AU915-928 region. Like [US915], it has 64 + 8 uplink channels divided into [sub-bands] and
+/// 8 downlink channels of 500 kHz, but with different data rates.
+///
+/// Uplinks are limited to a dwell time of 400 ms by default, which makes DR0 and DR1 unavailable.
+/// The network can lift this limit with a TxParamSetupReq.
+///
+/// [US915]: crate::radio::US915
+/// [sub-bands]: crate::radio::ChannelMask::sub_band
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AU915;
+
+const UPLINK_CHANNELS: [Hz; 72] = fixed_uplink_channels(915_200_000, 915_900_000);
+
+const DOWNLINK_CHANNELS: [Hz; 8] = channels(923_300_000, 600_000);
+
+impl Region for AU915 {
+    const JOIN_FREQUENCIES: &'static [Hz] = Self::TX_FREQUENCIES;
+
+    const TX_FREQUENCIES: &'static [Hz] = &UPLINK_CHANNELS;
+
+    const RX1_FREQUENCIES: &'static [Hz] = &DOWNLINK_CHANNELS;
+
+    const RX2_FREQUENCY: Hz = 923_300_000;
+
+    const RX2_DATA_RATE: usize = 8;
+
+    const DATA_RATES: &'static [Option<DataRate>] = &[
+        Some(DataRate::new(SpreadingFactor::Sf12, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf11, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf10, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf9, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf8, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf7, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf8, 500_000)),
+        None,
+        Some(DataRate::new(SpreadingFactor::Sf12, 500_000)),
+        Some(DataRate::new(SpreadingFactor::Sf11, 500_000)),
+        Some(DataRate::new(SpreadingFactor::Sf10, 500_000)),
+        Some(DataRate::new(SpreadingFactor::Sf9, 500_000)),
+        Some(DataRate::new(SpreadingFactor::Sf8, 500_000)),
+        Some(DataRate::new(SpreadingFactor::Sf7, 500_000)),
+    ];
+
+    const MAX_TX_DATA_RATE: usize = 6;
+
+    const MAX_EIRP: i8 = 30;
+
+    const TX_POWERS: &'static [u8] = &[0, 2, 4, 6, 8, 10, 12, 14, 16, 18, 20, 22, 24, 26, 28];
+
+    const MAX_PAYLOAD_SIZES: &'static [u8] = &[
+        59, 59, 59, 123, 250, 250, 250, 0, 61, 137, 250, 250, 250, 250,
+    ];
+
+    const MAX_PAYLOAD_SIZES_DWELL: &'static [u8] =
+        &[0, 0, 19, 61, 133, 250, 250, 0, 61, 137, 250, 250, 250, 250];
+
+    const UPLINK_DWELL_TIME: bool = true;
+
+    const TX_PARAM_SETUP: bool = true;
+
+    const JOIN_DATA_RATE: usize = 2;
+
+    const RX1_DR_OFFSETS: &'static [&'static [usize]] = &[
+        &[8, 8, 8, 8, 8, 8],
+        &[9, 8, 8, 8, 8, 8],
+        &[10, 9, 8, 8, 8, 8],
+        &[11, 10, 9, 8, 8, 8],
+        &[12, 11, 10, 9, 8, 8],
+        &[13, 12, 11, 10, 9, 8],
+        &[13, 13, 12, 11, 10, 9],
+        &[9, 8, 8, 8, 8, 8],
+    ];
+
+    const BEACON: BeaconParameters<'static> =
+        BeaconParameters::new(10, &DOWNLINK_CHANNELS, &DOWNLINK_CHANNELS, 3, 1);
+
+    const DUTY_CYCLE_BANDS: &'static [DutyCycleBand] = &[];
+
+    const CF_LIST_TYPE: CfListType = CfListType::ChannelMask;
+
+    const SUB_BANDS: &'static [ChannelMask] = &FIXED_SUB_BANDS;
+
+    fn supports_data_rate(channel: usize, dr: usize) -> bool {
+        match channel {
+            0..=63 => dr <= 5,
+            _ => dr == 6,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::radio::region::tests::assert_rx1_data_rates;
+    use crate::radio::RegionalParameters;
+
+    #[test]
+    fn has_fixed_channels() {
+        assert_eq!(AU915.channel_count(), 72);
+        let channels = [
+            (0, 915_200_000, 923_300_000),
+            (9, 917_000_000, 923_900_000),
+            (63, 927_800_000, 927_500_000),
+            (64, 915_900_000, 923_300_000),
+            (71, 927_100_000, 927_500_000),
+        ];
+        for (channel, tx, rx1) in channels {
+            assert_eq!(AU915.tx_frequency(channel), tx, "channel {}", channel);
+            assert_eq!(AU915.rx1_frequency(channel), rx1, "channel {}", channel);
+        }
+        assert!(AU915.supports_data_rate(0, 5));
+        assert!(!AU915.supports_data_rate(0, 6));
+        assert!(AU915.supports_data_rate(64, 6));
+    }
+
+    #[test]
+    fn reserves_dr7() {
+        assert_eq!(AU915.data_rate(7), None);
+        assert_eq!(AU915.max_mac_payload_size(7, false), 0);
+        assert_eq!(AU915.max_mac_payload_size(7, true), 0);
+    }
+
+    #[test]
+    fn limits_payload_sizes() {
+        let sizes = [
+            (0, 59, 0),
+            (2, 59, 19),
+            (3, 123, 61),
+            (4, 250, 133),
+            (6, 250, 250),
+        ];
+        for (dr, m, m_dwell) in sizes {
+            assert_eq!(AU915.max_mac_payload_size(dr, false), m, "DR{}", dr);
+            assert_eq!(AU915.max_mac_payload_size(dr, true), m_dwell, "DR{}", dr);
+        }
+    }
+
+    #[test]
+    fn answers_on_500_khz_data_rates() {
+        assert_rx1_data_rates(
+            &AU915,
+            false,
+            &[
+                (0, 0, Some(8)),
+                (2, 1, Some(9)),
+                (5, 0, Some(13)),
+                (5, 5, Some(8)),
+                (6, 0, Some(13)),
+                (6, 5, Some(9)),
+                (6, 6, None),
+            ],
+        );
+        assert_eq!(AU915.beacon().data_rate(), 10);
+        assert_eq!(
+            AU915.data_rate(10),
+            Some(&DataRate::new(SpreadingFactor::Sf10, 500_000))
+        );
+    }
+}
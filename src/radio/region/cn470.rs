@@ -0,0 +1,264 @@
+use radio::modulation::lora::SpreadingFactor;
+
+use crate::radio::{
+    BeaconParameters, CfListType, ChannelMask, DataRate, DutyCycleBand, Hz, Region,
+};
+
+/// The CN470-510 region. It has one of four channel plans, depending on the frequency of the
+/// crystal of the gateways, and the plans are not compatible with each other. The plan is selected
+/// through the `PLAN` parameter; use one of the type aliases, or their constants as values,
+/// instead.
+///
+/// Plans for 20 MHz crystals have 64 uplink channels, those for 26 MHz crystals have 48. The
+/// channels are not listed, but computed from their index.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CN470<const PLAN: u8>;
+
+/// CN470 for gateways with a 20 MHz crystal, with downlinks on the uplink channel.
+pub type CN470_20A = CN470<0>;
+/// CN470 plan 20A as a value, written like the other regions, e.g.
+/// `Settings::default_for(CN470_20A)`.
+pub const CN470_20A: CN470_20A = CN470;
+/// CN470 for gateways with a 20 MHz crystal, with downlinks on a separate channel.
+pub type CN470_20B = CN470<1>;
+/// CN470 plan 20B as a value.
+pub const CN470_20B: CN470_20B = CN470;
+/// CN470 for gateways with a 26 MHz crystal, with uplinks from 470.3 MHz.
+pub type CN470_26A = CN470<2>;
+/// CN470 plan 26A as a value.
+pub const CN470_26A: CN470_26A = CN470;
+/// CN470 for gateways with a 26 MHz crystal, with uplinks from 480.3 MHz.
+pub type CN470_26B = CN470<3>;
+/// CN470 plan 26B as a value.
+pub const CN470_26B: CN470_26B = CN470;
+
+/// The spacing between channels.
+const STEP: Hz = 200_000;
+
+const fn frequency(first: Hz, index: usize) -> Hz {
+    first + STEP * index as Hz
+}
+
+const fn rx2_frequency(plan: u8) -> Hz {
+    match plan {
+        0 => 485_300_000,
+        1 => 486_900_000,
+        2 => 492_500_000,
+        _ => 502_500_000,
+    }
+}
+
+const fn beacon_frequency(plan: u8) -> Hz {
+    match plan {
+        0 => 485_300_000,
+        1 => 486_900_000,
+        2 => 494_900_000,
+        _ => 504_900_000,
+    }
+}
+
+/// Groups of eight uplink channels, for scanning.
+const fn sub_bands<const N: usize>() -> [ChannelMask; N] {
+    const NONE: ChannelMask = ChannelMask::none();
+    let mut sub_bands = [NONE; N];
+    let mut i = 0;
+    while i < N {
+        sub_bands[i] = ChannelMask::range(8 * i, 8);
+        i += 1;
+    }
+    sub_bands
+}
+
+const SUB_BANDS_20: [ChannelMask; 8] = sub_bands();
+
+const SUB_BANDS_26: [ChannelMask; 6] = sub_bands();
+
+impl<const PLAN: u8> Region for CN470<PLAN> {
+    const RX2_FREQUENCY: Hz = rx2_frequency(PLAN);
+
+    const RX2_DATA_RATE: usize = 1;
+
+    const DATA_RATES: &'static [Option<DataRate>] = &[
+        Some(DataRate::new(SpreadingFactor::Sf12, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf11, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf10, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf9, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf8, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf7, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf7, 500_000)),
+        Some(DataRate::fsk(50_000)),
+    ];
+
+    const MAX_TX_DATA_RATE: usize = 7;
+
+    const MAX_EIRP: i8 = 19;
+
+    const TX_POWERS: &'static [u8] = &[0, 2, 4, 6, 8, 10, 12, 14];
+
+    const MAX_PAYLOAD_SIZES: &'static [u8] = &[0, 31, 94, 192, 250, 250, 250, 250];
+
+    const JOIN_DATA_RATE: usize = 1;
+
+    const RX1_DR_OFFSETS: &'static [&'static [usize]] = &[
+        &[1, 1, 1, 1, 1, 1],
+        &[1, 1, 1, 1, 1, 1],
+        &[2, 1, 1, 1, 1, 1],
+        &[3, 2, 1, 1, 1, 1],
+        &[4, 3, 2, 1, 1, 1],
+        &[5, 4, 3, 2, 1, 1],
+        &[6, 5, 4, 3, 2, 1],
+        &[7, 6, 5, 4, 3, 2],
+    ];
+
+    const BEACON: BeaconParameters<'static> = BeaconParameters::new(
+        2,
+        &[beacon_frequency(PLAN)],
+        &[beacon_frequency(PLAN)],
+        3,
+        1,
+    );
+
+    const DUTY_CYCLE_BANDS: &'static [DutyCycleBand] = &[];
+
+    const CF_LIST_TYPE: CfListType = CfListType::ChannelMask;
+
+    const SUB_BANDS: &'static [ChannelMask] = match PLAN {
+        0 | 1 => &SUB_BANDS_20,
+        _ => &SUB_BANDS_26,
+    };
+
+    fn channel_count() -> usize {
+        match PLAN {
+            0 | 1 => 64,
+            _ => 48,
+        }
+    }
+
+    fn tx_frequency(channel: usize) -> Hz {
+        match (PLAN, channel) {
+            (0, 0..=31) => frequency(470_300_000, channel),
+            (0, _) => frequency(503_500_000, channel - 32),
+            (1, 0..=31) => frequency(476_900_000, channel),
+            (1, _) => frequency(496_900_000, channel - 32),
+            (2, _) => frequency(470_300_000, channel),
+            (_, _) => frequency(480_300_000, channel),
+        }
+    }
+
+    fn rx1_frequency(channel: usize) -> Hz {
+        match (PLAN, channel) {
+            (0, _) => Self::tx_frequency(channel),
+            (1, 0..=31) => frequency(490_100_000, channel),
+            (1, _) => frequency(503_500_000, channel - 32),
+            (2, _) => frequency(490_100_000, channel % 24),
+            (_, _) => frequency(500_100_000, channel % 24),
+        }
+    }
+
+    /// The common join channels of the plan, which every network listens on.
+    fn join_channel_mask() -> ChannelMask {
+        let channels: &[usize] = match PLAN {
+            0 => &[3, 11, 19, 27, 35, 43, 51, 59],
+            1 => &[15, 47],
+            _ => &[0, 10, 20, 30, 40],
+        };
+
+        let mut mask = ChannelMask::none();
+        for &channel in channels {
+            mask.set(channel, true);
+        }
+        mask
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::radio::region::tests::assert_rx1_data_rates;
+    use crate::radio::RegionalParameters;
+
+    /// Checks the number of channels of a plan, the uplink and RX1 frequency of some of them, given
+    /// as (channel, uplink, RX1), and the frequencies of RX2 and the beacon.
+    fn assert_plan(
+        region: &dyn RegionalParameters,
+        count: usize,
+        channels: &[(usize, Hz, Hz)],
+        rx2: Hz,
+        beacon: Hz,
+    ) {
+        assert_eq!(region.channel_count(), count);
+        for &(channel, tx, rx1) in channels {
+            assert_eq!(region.tx_frequency(channel), tx, "channel {}", channel);
+            assert_eq!(region.rx1_frequency(channel), rx1, "channel {}", channel);
+        }
+        assert_eq!((region.rx2_frequency(), region.rx2_data_rate()), (rx2, 1));
+        assert_eq!(region.beacon().frequencies(), &[beacon]);
+    }
+
+    #[test]
+    fn computes_channels_of_each_plan() {
+        let channels = [
+            (0, 470_300_000, 470_300_000),
+            (31, 476_500_000, 476_500_000),
+            (32, 503_500_000, 503_500_000),
+            (63, 509_700_000, 509_700_000),
+        ];
+        assert_plan(&CN470_20A, 64, &channels, 485_300_000, 485_300_000);
+
+        let channels = [
+            (0, 476_900_000, 490_100_000),
+            (31, 483_100_000, 496_300_000),
+            (32, 496_900_000, 503_500_000),
+            (63, 503_100_000, 509_700_000),
+        ];
+        assert_plan(&CN470_20B, 64, &channels, 486_900_000, 486_900_000);
+
+        let channels = [
+            (0, 470_300_000, 490_100_000),
+            (23, 474_900_000, 494_700_000),
+            (24, 475_100_000, 490_100_000),
+            (47, 479_700_000, 494_700_000),
+        ];
+        assert_plan(&CN470_26A, 48, &channels, 492_500_000, 494_900_000);
+
+        let channels = [
+            (0, 480_300_000, 500_100_000),
+            (23, 484_900_000, 504_700_000),
+            (24, 485_100_000, 500_100_000),
+            (47, 489_700_000, 504_700_000),
+        ];
+        assert_plan(&CN470_26B, 48, &channels, 502_500_000, 504_900_000);
+    }
+
+    #[test]
+    fn joins_on_common_channels() {
+        let mask = <CN470_20A as Region>::join_channel_mask();
+        assert!(mask.channels().eq([3, 11, 19, 27, 35, 43, 51, 59]));
+        let mask = <CN470_20B as Region>::join_channel_mask();
+        assert!(mask.channels().eq([15, 47]));
+        let mask = <CN470_26B as Region>::join_channel_mask();
+        assert!(mask.channels().eq([0, 10, 20, 30, 40]));
+    }
+
+    #[test]
+    fn never_uses_dr0() {
+        let region = CN470_20A;
+        assert_eq!(region.join_data_rate(), 1);
+        let sizes = [(0, 0), (1, 31), (2, 94), (3, 192), (4, 250), (7, 250)];
+        for (dr, m) in sizes {
+            assert_eq!(region.max_mac_payload_size(dr, false), m, "DR{}", dr);
+        }
+        assert_rx1_data_rates(
+            &region,
+            false,
+            &[
+                (1, 0, Some(1)),
+                (2, 3, Some(1)),
+                (3, 1, Some(2)),
+                (5, 5, Some(1)),
+                (7, 2, Some(5)),
+            ],
+        );
+    }
+}
@@ -0,0 +1,548 @@
+use radio::modulation::lora::CodingRate;
+
+use crate::radio::{
+    BeaconParameters, CfListType, ChannelMask, DataRate, DutyCycleBand, Hz, ListenBeforeTalk,
+    RegionalParameters,
+};
+
+/// The frequency ranges LoRa radios can operate in.
+const FREQUENCY_RANGES: [(Hz, Hz); 2] =
+    [(137_000_000, 1_020_000_000), (2_400_000_000, 2_500_000_000)];
+
+/// The highest number of data rates, as data rates are sent as 4-bit indices.
+const MAX_DATA_RATES: usize = 16;
+
+/// The highest number of TX power levels, as they are sent as 4-bit indices.
+const MAX_TX_POWERS: usize = 16;
+
+/// The largest MAC payload size any data rate can have, which fills a PHY payload of 255 bytes.
+const MAX_PAYLOAD_SIZE: u8 = 250;
+
+/// The regional parameters of a private network, e.g. loaded from configuration at boot. Turn it
+/// into a region with [CustomRegion::new].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChannelPlan<'a> {
+    /// The frequencies of the uplink channels, indexed by channel.
+    pub tx_frequencies: &'a [Hz],
+    /// The RX1 frequency for each uplink channel. If this is shorter than `tx_frequencies`, it
+    /// wraps around; if it is empty, RX1 uses the uplink frequency.
+    pub rx1_frequencies: &'a [Hz],
+    pub rx2_frequency: Hz,
+    pub rx2_data_rate: usize,
+    /// The data rates, indexed by DR. Reserved data rates are `None`.
+    pub data_rates: &'a [Option<DataRate>],
+    pub coding_rate: CodingRate,
+    /// Whether LoRa transmissions use long interleaving on top of `coding_rate`.
+    pub long_interleaving: bool,
+    pub max_tx_data_rate: usize,
+    pub join_data_rate: usize,
+    /// The maximum MAC payload size (M) per data rate.
+    pub max_payload_sizes: &'a [u8],
+    pub max_eirp: i8,
+    /// The attenuation in dB below `max_eirp` for each TXPower index.
+    pub tx_powers: &'a [u8],
+    pub duty_cycle_bands: &'a [DutyCycleBand],
+    pub listen_before_talk: Option<ListenBeforeTalk>,
+    pub beacon: BeaconParameters<'a>,
+    pub cf_list_type: CfListType,
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for ChannelPlan<'_> {
+    fn format(&self, f: defmt::Formatter) {
+        // The data rates and coding rate come from the radio crate, which does not support defmt
+        defmt::write!(
+            f,
+            "ChannelPlan {{ tx_frequencies: {}, rx1_frequencies: {}, rx2_frequency: {}, ",
+            self.tx_frequencies,
+            self.rx1_frequencies,
+            self.rx2_frequency,
+        );
+        defmt::write!(
+            f,
+            "rx2_data_rate: {}, data_rates: {}, coding_rate: {}, long_interleaving: {}, ",
+            self.rx2_data_rate,
+            defmt::Debug2Format(self.data_rates),
+            defmt::Debug2Format(&self.coding_rate),
+            self.long_interleaving,
+        );
+        defmt::write!(
+            f,
+            "max_tx_data_rate: {}, join_data_rate: {}, max_payload_sizes: {}, max_eirp: {}, ",
+            self.max_tx_data_rate,
+            self.join_data_rate,
+            self.max_payload_sizes,
+            self.max_eirp,
+        );
+        defmt::write!(
+            f,
+            "tx_powers: {}, duty_cycle_bands: {}, listen_before_talk: {}, beacon: {}, ",
+            self.tx_powers,
+            self.duty_cycle_bands,
+            self.listen_before_talk,
+            self.beacon,
+        );
+        defmt::write!(f, "cf_list_type: {} }}", self.cf_list_type);
+    }
+}
+
+/// A region built from a [ChannelPlan] that has been checked for consistency.
+///
+/// The RX1 data rate is the uplink data rate minus the RX1 data rate offset, as in most regions.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CustomRegion<'a>(ChannelPlan<'a>);
+
+impl<'a> CustomRegion<'a> {
+    /// Validates `plan`, and creates a region from it.
+    pub fn new(plan: ChannelPlan<'a>) -> Result<Self, ChannelPlanError> {
+        if plan.tx_frequencies.is_empty() {
+            return Err(ChannelPlanError::NoChannels);
+        }
+        if plan.tx_frequencies.len() > ChannelMask::MAX_CHANNELS {
+            return Err(ChannelPlanError::TooManyChannels);
+        }
+
+        let rx2 = [plan.rx2_frequency];
+        let frequencies = plan
+            .tx_frequencies
+            .iter()
+            .chain(plan.rx1_frequencies)
+            .chain(&rx2)
+            .chain(plan.beacon.frequencies())
+            .chain(plan.beacon.ping_slot_frequencies());
+        for &frequency in frequencies {
+            if !FREQUENCY_RANGES
+                .iter()
+                .any(|&(min, max)| (min..=max).contains(&frequency))
+            {
+                return Err(ChannelPlanError::InvalidFrequency(frequency));
+            }
+        }
+
+        if plan.beacon.frequencies().is_empty() || plan.beacon.ping_slot_frequencies().is_empty() {
+            return Err(ChannelPlanError::NoBeaconFrequencies);
+        }
+
+        if plan.data_rates.len() > MAX_DATA_RATES {
+            return Err(ChannelPlanError::TooManyDataRates);
+        }
+        // The symbol time is divided by these
+        let unusable = |data_rate: &DataRate| {
+            matches!(
+                *data_rate,
+                DataRate::LoRa { bandwidth: 0, .. } | DataRate::Fsk { bitrate: 0 }
+            )
+        };
+        if let Some(dr) = plan
+            .data_rates
+            .iter()
+            .position(|data_rate| data_rate.as_ref().is_some_and(unusable))
+        {
+            return Err(ChannelPlanError::InvalidModulation(dr));
+        }
+        let data_rates = [
+            plan.rx2_data_rate,
+            plan.max_tx_data_rate,
+            plan.join_data_rate,
+            plan.beacon.data_rate(),
+        ];
+        for dr in data_rates {
+            if !matches!(plan.data_rates.get(dr), Some(Some(_))) {
+                return Err(ChannelPlanError::InvalidDataRate(dr));
+            }
+        }
+        if plan.join_data_rate > plan.max_tx_data_rate {
+            return Err(ChannelPlanError::InvalidDataRate(plan.join_data_rate));
+        }
+        if plan.data_rates[plan.rx2_data_rate]
+            .as_ref()
+            .is_some_and(DataRate::is_uplink_only)
+        {
+            return Err(ChannelPlanError::UplinkOnlyDataRate(plan.rx2_data_rate));
+        }
+        // Beacons are only defined for LoRa
+        let beacon_dr = plan.beacon.data_rate();
+        if !matches!(plan.data_rates[beacon_dr], Some(DataRate::LoRa { .. })) {
+            return Err(ChannelPlanError::InvalidBeaconDataRate(beacon_dr));
+        }
+        if plan.max_payload_sizes.len() != plan.data_rates.len()
+            || plan
+                .max_payload_sizes
+                .iter()
+                .any(|&size| size > MAX_PAYLOAD_SIZE)
+        {
+            return Err(ChannelPlanError::PayloadSizes);
+        }
+
+        if plan.tx_powers.is_empty() || plan.tx_powers.len() > MAX_TX_POWERS {
+            return Err(ChannelPlanError::TxPowers);
+        }
+
+        if plan
+            .duty_cycle_bands
+            .iter()
+            .any(|band| band.min() > band.max() || band.duty_cycle() == 0)
+        {
+            return Err(ChannelPlanError::InvalidDutyCycleBand);
+        }
+
+        Ok(CustomRegion(plan))
+    }
+
+    pub fn plan(&self) -> &ChannelPlan<'a> {
+        &self.0
+    }
+}
+
+impl RegionalParameters for CustomRegion<'_> {
+    fn channel_count(&self) -> usize {
+        self.0.tx_frequencies.len()
+    }
+
+    fn tx_frequency(&self, channel: usize) -> Hz {
+        self.0.tx_frequencies[channel]
+    }
+
+    fn rx1_frequency(&self, channel: usize) -> Hz {
+        match self.0.rx1_frequencies.len() {
+            0 => self.tx_frequency(channel),
+            len => self.0.rx1_frequencies[channel % len],
+        }
+    }
+
+    fn rx2_frequency(&self) -> Hz {
+        self.0.rx2_frequency
+    }
+
+    fn rx2_data_rate(&self) -> usize {
+        self.0.rx2_data_rate
+    }
+
+    fn data_rate(&self, dr: usize) -> Option<&DataRate> {
+        self.0.data_rates.get(dr).and_then(Option::as_ref)
+    }
+
+    fn coding_rate(&self) -> CodingRate {
+        self.0.coding_rate
+    }
+
+    fn long_interleaving(&self) -> bool {
+        self.0.long_interleaving
+    }
+
+    fn max_tx_data_rate(&self) -> usize {
+        self.0.max_tx_data_rate
+    }
+
+    fn max_eirp(&self) -> i8 {
+        self.0.max_eirp
+    }
+
+    fn tx_powers(&self) -> &[u8] {
+        self.0.tx_powers
+    }
+
+    fn max_mac_payload_size(&self, dr: usize, _dwell_time: bool) -> usize {
+        self.0.max_payload_sizes.get(dr).copied().unwrap_or(0) as usize
+    }
+
+    fn uplink_dwell_time(&self) -> bool {
+        false
+    }
+
+    fn downlink_dwell_time(&self) -> bool {
+        false
+    }
+
+    fn tx_param_setup(&self) -> bool {
+        false
+    }
+
+    fn join_data_rate(&self) -> usize {
+        self.0.join_data_rate
+    }
+
+    fn rx1_data_rate(
+        &self,
+        tx_dr: usize,
+        rx1_dr_offset: usize,
+        _dwell_time: bool,
+    ) -> Option<usize> {
+        let dr = tx_dr.saturating_sub(rx1_dr_offset);
+        (tx_dr <= self.0.max_tx_data_rate && self.data_rate(dr).is_some()).then_some(dr)
+    }
+
+    fn beacon(&self) -> &BeaconParameters<'_> {
+        &self.0.beacon
+    }
+
+    fn duty_cycle_bands(&self) -> &[DutyCycleBand] {
+        self.0.duty_cycle_bands
+    }
+
+    fn cf_list_type(&self) -> CfListType {
+        self.0.cf_list_type
+    }
+
+    fn sub_bands(&self) -> &[ChannelMask] {
+        &[]
+    }
+
+    fn listen_before_talk(&self) -> Option<&ListenBeforeTalk> {
+        self.0.listen_before_talk.as_ref()
+    }
+
+    fn supports_data_rate(&self, _channel: usize, dr: usize) -> bool {
+        dr <= self.0.max_tx_data_rate && self.data_rate(dr).is_some()
+    }
+
+    fn default_channel_mask(&self) -> ChannelMask {
+        ChannelMask::first(self.channel_count())
+    }
+
+    fn join_channel_mask(&self) -> ChannelMask {
+        self.default_channel_mask()
+    }
+}
+
+/// The reason a [ChannelPlan] was rejected.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ChannelPlanError {
+    /// The plan has no uplink channels.
+    NoChannels,
+    /// The plan has more uplink channels than a [ChannelMask] can describe.
+    TooManyChannels,
+    /// The frequency is outside the ranges LoRa radios operate in.
+    InvalidFrequency(Hz),
+    /// The beacon or the ping slots have no frequencies.
+    NoBeaconFrequencies,
+    /// The plan has more than 16 data rates.
+    TooManyDataRates,
+    /// The data rate has a bandwidth or bitrate of zero.
+    InvalidModulation(usize),
+    /// The data rate index refers to a reserved or missing data rate, or exceeds the maximum
+    /// uplink data rate.
+    InvalidDataRate(usize),
+    /// The RX2 data rate can only be used for uplinks, e.g. LR-FHSS.
+    UplinkOnlyDataRate(usize),
+    /// The beacon data rate is not a LoRa data rate.
+    InvalidBeaconDataRate(usize),
+    /// The number of maximum payload sizes does not match the number of data rates, or a size
+    /// exceeds 250 bytes.
+    PayloadSizes,
+    /// The plan has no TX power levels, or more than 16.
+    TxPowers,
+    /// A duty-cycle band ends before it starts, or has a duty cycle of zero.
+    InvalidDutyCycleBand,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::radio::LrFhssCodingRate;
+    use radio::modulation::lora::SpreadingFactor;
+
+    const DATA_RATES: [Option<DataRate>; 6] = [
+        Some(DataRate::new(SpreadingFactor::Sf12, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf11, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf10, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf9, 125_000)),
+        None,
+        Some(DataRate::new(SpreadingFactor::Sf7, 125_000)),
+    ];
+
+    const RESERVED: Option<DataRate> = None;
+
+    /// Returns [DATA_RATES] with DR5 replaced by `data_rate`.
+    const fn with_dr5(data_rate: DataRate) -> [Option<DataRate>; 6] {
+        let mut data_rates = DATA_RATES;
+        data_rates[5] = Some(data_rate);
+        data_rates
+    }
+
+    const ZERO_BANDWIDTH: [Option<DataRate>; 6] = with_dr5(DataRate::new(SpreadingFactor::Sf7, 0));
+
+    const ZERO_BITRATE: [Option<DataRate>; 6] = with_dr5(DataRate::fsk(0));
+
+    const LR_FHSS: [Option<DataRate>; 6] =
+        with_dr5(DataRate::lr_fhss(LrFhssCodingRate::Cr1_3, 137_000, 3_900));
+
+    const FSK: [Option<DataRate>; 6] = with_dr5(DataRate::fsk(50_000));
+
+    const TOO_MANY_DATA_RATES: [Option<DataRate>; MAX_DATA_RATES + 1] =
+        [RESERVED; MAX_DATA_RATES + 1];
+
+    const TOO_MANY_CHANNELS: [Hz; ChannelMask::MAX_CHANNELS + 1] =
+        [868_100_000; ChannelMask::MAX_CHANNELS + 1];
+
+    const DUTY_CYCLE_BANDS: [DutyCycleBand; 1] =
+        [DutyCycleBand::new(863_000_000, 870_000_000, 100)];
+
+    const EMPTY_BAND: [DutyCycleBand; 1] = [DutyCycleBand::new(870_000_000, 863_000_000, 100)];
+
+    const UNUSABLE_BAND: [DutyCycleBand; 1] = [DutyCycleBand::new(863_000_000, 870_000_000, 0)];
+
+    fn plan() -> ChannelPlan<'static> {
+        ChannelPlan {
+            tx_frequencies: &[868_100_000, 868_300_000, 868_500_000],
+            rx1_frequencies: &[],
+            rx2_frequency: 869_525_000,
+            rx2_data_rate: 0,
+            data_rates: &DATA_RATES,
+            coding_rate: CodingRate::Cr4_5,
+            long_interleaving: false,
+            max_tx_data_rate: 5,
+            join_data_rate: 0,
+            max_payload_sizes: &[59, 59, 59, 123, 0, 250],
+            max_eirp: 16,
+            tx_powers: &[0, 2, 4, 6, 8, 10, 12, 14],
+            duty_cycle_bands: &DUTY_CYCLE_BANDS,
+            listen_before_talk: None,
+            beacon: BeaconParameters::new(3, &[869_525_000], &[869_525_000], 2, 0),
+            cf_list_type: CfListType::Frequencies,
+        }
+    }
+
+    /// A change to a valid plan.
+    type Change = fn(&mut ChannelPlan<'static>);
+
+    /// Changes to a valid plan, and the error each of them causes.
+    const INVALID_PLANS: &[(Change, ChannelPlanError)] = &[
+        (
+            |plan| plan.tx_frequencies = &[],
+            ChannelPlanError::NoChannels,
+        ),
+        (
+            |plan| plan.tx_frequencies = &TOO_MANY_CHANNELS,
+            ChannelPlanError::TooManyChannels,
+        ),
+        (
+            |plan| plan.rx1_frequencies = &[869_000_000, 100_000_000],
+            ChannelPlanError::InvalidFrequency(100_000_000),
+        ),
+        (
+            |plan| plan.rx2_frequency = 2_600_000_000,
+            ChannelPlanError::InvalidFrequency(2_600_000_000),
+        ),
+        (
+            |plan| plan.beacon = BeaconParameters::new(3, &[869_525_000], &[0], 2, 0),
+            ChannelPlanError::InvalidFrequency(0),
+        ),
+        (
+            |plan| plan.beacon = BeaconParameters::new(3, &[], &[869_525_000], 2, 0),
+            ChannelPlanError::NoBeaconFrequencies,
+        ),
+        (
+            |plan| plan.beacon = BeaconParameters::new(3, &[869_525_000], &[], 2, 0),
+            ChannelPlanError::NoBeaconFrequencies,
+        ),
+        (
+            |plan| plan.data_rates = &TOO_MANY_DATA_RATES,
+            ChannelPlanError::TooManyDataRates,
+        ),
+        (
+            |plan| plan.data_rates = &ZERO_BANDWIDTH,
+            ChannelPlanError::InvalidModulation(5),
+        ),
+        (
+            |plan| plan.data_rates = &ZERO_BITRATE,
+            ChannelPlanError::InvalidModulation(5),
+        ),
+        (
+            |plan| plan.rx2_data_rate = 4,
+            ChannelPlanError::InvalidDataRate(4),
+        ),
+        (
+            |plan| plan.max_tx_data_rate = 6,
+            ChannelPlanError::InvalidDataRate(6),
+        ),
+        (
+            |plan| plan.join_data_rate = 6,
+            ChannelPlanError::InvalidDataRate(6),
+        ),
+        (
+            |plan| {
+                plan.join_data_rate = 3;
+                plan.max_tx_data_rate = 2;
+            },
+            ChannelPlanError::InvalidDataRate(3),
+        ),
+        (
+            |plan| plan.beacon = BeaconParameters::new(4, &[869_525_000], &[869_525_000], 2, 0),
+            ChannelPlanError::InvalidDataRate(4),
+        ),
+        (
+            |plan| {
+                plan.data_rates = &LR_FHSS;
+                plan.rx2_data_rate = 5;
+            },
+            ChannelPlanError::UplinkOnlyDataRate(5),
+        ),
+        (
+            |plan| {
+                plan.data_rates = &FSK;
+                plan.beacon = BeaconParameters::new(5, &[869_525_000], &[869_525_000], 2, 0);
+            },
+            ChannelPlanError::InvalidBeaconDataRate(5),
+        ),
+        (
+            |plan| plan.max_payload_sizes = &[59, 59, 59, 123, 0],
+            ChannelPlanError::PayloadSizes,
+        ),
+        (
+            |plan| plan.max_payload_sizes = &[59, 59, 59, 123, 0, 251],
+            ChannelPlanError::PayloadSizes,
+        ),
+        (|plan| plan.tx_powers = &[], ChannelPlanError::TxPowers),
+        (
+            |plan| plan.tx_powers = &[0; MAX_TX_POWERS + 1],
+            ChannelPlanError::TxPowers,
+        ),
+        (
+            |plan| plan.duty_cycle_bands = &EMPTY_BAND,
+            ChannelPlanError::InvalidDutyCycleBand,
+        ),
+        (
+            |plan| plan.duty_cycle_bands = &UNUSABLE_BAND,
+            ChannelPlanError::InvalidDutyCycleBand,
+        ),
+    ];
+
+    #[test]
+    fn accepts_valid_plan() {
+        let region = CustomRegion::new(plan()).unwrap();
+        assert_eq!(region.channel_count(), 3);
+        assert_eq!(region.rx1_frequency(2), 868_500_000);
+        assert_eq!(region.max_mac_payload_size(5, false), 250);
+        assert_eq!(region.rx1_data_rate(5, 2, false), Some(3));
+        // DR4 is reserved
+        assert_eq!(region.rx1_data_rate(5, 1, false), None);
+        assert!(!region.supports_data_rate(0, 4));
+    }
+
+    #[test]
+    fn wraps_rx1_frequencies() {
+        let mut plan = plan();
+        plan.rx1_frequencies = &[869_000_000, 869_200_000];
+        let region = CustomRegion::new(plan).unwrap();
+        assert_eq!(region.rx1_frequency(0), 869_000_000);
+        assert_eq!(region.rx1_frequency(1), 869_200_000);
+        assert_eq!(region.rx1_frequency(2), 869_000_000);
+    }
+
+    #[test]
+    fn rejects_invalid_plans() {
+        for (i, (change, error)) in INVALID_PLANS.iter().enumerate() {
+            let mut plan = plan();
+            change(&mut plan);
+            assert_eq!(
+                CustomRegion::new(plan).err().as_ref(),
+                Some(error),
+                "case {}",
+                i
+            );
+        }
+    }
+}
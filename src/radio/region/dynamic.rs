@@ -0,0 +1,170 @@
+use radio::modulation::lora::CodingRate;
+
+use crate::radio::region::forward_regional_parameters;
+use crate::radio::{
+    BeaconParameters, CfListType, ChannelMask, DataRate, DutyCycleBand, Hz, ListenBeforeTalk,
+    RegionalParameters, AS923_1, AS923_1JP, AS923_2, AS923_3, AS923_4, AU915, CN470_20A, CN470_20B,
+    CN470_26A, CN470_26B, CN779, EU433, EU868, IN865, ISM2400, KR920, RU864, US915,
+};
+
+/// One of the built-in regions, chosen at runtime. This allows a single firmware image to select
+/// its region at boot, e.g. from configuration.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DynamicRegion {
+    AS923_1,
+    AS923_1JP,
+    AS923_2,
+    AS923_3,
+    AS923_4,
+    AU915,
+    CN470_20A,
+    CN470_20B,
+    CN470_26A,
+    CN470_26B,
+    CN779,
+    EU433,
+    EU868,
+    IN865,
+    ISM2400,
+    KR920,
+    RU864,
+    US915,
+}
+
+impl DynamicRegion {
+    fn parameters(&self) -> &'static dyn RegionalParameters {
+        match self {
+            DynamicRegion::AS923_1 => &AS923_1,
+            DynamicRegion::AS923_1JP => &AS923_1JP,
+            DynamicRegion::AS923_2 => &AS923_2,
+            DynamicRegion::AS923_3 => &AS923_3,
+            DynamicRegion::AS923_4 => &AS923_4,
+            DynamicRegion::AU915 => &AU915,
+            DynamicRegion::CN470_20A => &CN470_20A,
+            DynamicRegion::CN470_20B => &CN470_20B,
+            DynamicRegion::CN470_26A => &CN470_26A,
+            DynamicRegion::CN470_26B => &CN470_26B,
+            DynamicRegion::CN779 => &CN779,
+            DynamicRegion::EU433 => &EU433,
+            DynamicRegion::EU868 => &EU868,
+            DynamicRegion::IN865 => &IN865,
+            DynamicRegion::ISM2400 => &ISM2400,
+            DynamicRegion::KR920 => &KR920,
+            DynamicRegion::RU864 => &RU864,
+            DynamicRegion::US915 => &US915,
+        }
+    }
+}
+
+impl RegionalParameters for DynamicRegion {
+    forward_regional_parameters!(self => self.parameters());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REGIONS: &[(DynamicRegion, &dyn RegionalParameters)] = &[
+        (DynamicRegion::AS923_1, &AS923_1),
+        (DynamicRegion::AS923_1JP, &AS923_1JP),
+        (DynamicRegion::AS923_2, &AS923_2),
+        (DynamicRegion::AS923_3, &AS923_3),
+        (DynamicRegion::AS923_4, &AS923_4),
+        (DynamicRegion::AU915, &AU915),
+        (DynamicRegion::CN470_20A, &CN470_20A),
+        (DynamicRegion::CN470_20B, &CN470_20B),
+        (DynamicRegion::CN470_26A, &CN470_26A),
+        (DynamicRegion::CN470_26B, &CN470_26B),
+        (DynamicRegion::CN779, &CN779),
+        (DynamicRegion::EU433, &EU433),
+        (DynamicRegion::EU868, &EU868),
+        (DynamicRegion::IN865, &IN865),
+        (DynamicRegion::ISM2400, &ISM2400),
+        (DynamicRegion::KR920, &KR920),
+        (DynamicRegion::RU864, &RU864),
+        (DynamicRegion::US915, &US915),
+    ];
+
+    #[test]
+    fn forwards_to_static_region() {
+        for &(dynamic, region) in REGIONS {
+            assert_eq!(
+                dynamic.channel_count(),
+                region.channel_count(),
+                "{:?}",
+                dynamic
+            );
+            for channel in 0..region.channel_count() {
+                assert_eq!(dynamic.tx_frequency(channel), region.tx_frequency(channel));
+                assert_eq!(
+                    dynamic.rx1_frequency(channel),
+                    region.rx1_frequency(channel)
+                );
+            }
+            assert_eq!(
+                dynamic.rx2_frequency(),
+                region.rx2_frequency(),
+                "{:?}",
+                dynamic
+            );
+            assert_eq!(
+                dynamic.rx2_data_rate(),
+                region.rx2_data_rate(),
+                "{:?}",
+                dynamic
+            );
+            assert_eq!(
+                dynamic.join_data_rate(),
+                region.join_data_rate(),
+                "{:?}",
+                dynamic
+            );
+            assert_eq!(dynamic.max_eirp(), region.max_eirp(), "{:?}", dynamic);
+            assert_eq!(dynamic.beacon(), region.beacon(), "{:?}", dynamic);
+            assert_eq!(
+                dynamic.cf_list_type(),
+                region.cf_list_type(),
+                "{:?}",
+                dynamic
+            );
+            assert_eq!(
+                dynamic.duty_cycle_bands(),
+                region.duty_cycle_bands(),
+                "{:?}",
+                dynamic
+            );
+            assert_eq!(
+                dynamic.listen_before_talk(),
+                region.listen_before_talk(),
+                "{:?}",
+                dynamic
+            );
+            assert_eq!(
+                dynamic.join_channel_mask(),
+                region.join_channel_mask(),
+                "{:?}",
+                dynamic
+            );
+            for dr in 0..16 {
+                assert_eq!(dynamic.data_rate(dr), region.data_rate(dr), "{:?}", dynamic);
+                for dwell_time in [false, true] {
+                    assert_eq!(
+                        dynamic.max_mac_payload_size(dr, dwell_time),
+                        region.max_mac_payload_size(dr, dwell_time),
+                        "{:?} DR{}",
+                        dynamic,
+                        dr
+                    );
+                    assert_eq!(
+                        dynamic.rx1_data_rate(dr, 1, dwell_time),
+                        region.rx1_data_rate(dr, 1, dwell_time),
+                        "{:?} DR{}",
+                        dynamic,
+                        dr
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use radio::modulation::lora::SpreadingFactor;
+
+use crate::radio::{BeaconParameters, CfListType, DataRate, DutyCycleBand, Hz, Region};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct EU433;
+
+impl Region for EU433 {
+    const JOIN_FREQUENCIES: &'static [Hz] = &[433_175_000, 433_375_000, 433_575_000];
+
+    const TX_FREQUENCIES: &'static [Hz] = Self::JOIN_FREQUENCIES;
+
+    const RX1_FREQUENCIES: &'static [Hz] = Self::TX_FREQUENCIES;
+
+    const RX2_FREQUENCY: Hz = 434_665_000;
+
+    const RX2_DATA_RATE: usize = 0;
+
+    const DATA_RATES: &'static [Option<DataRate>] = &[
+        Some(DataRate::new(SpreadingFactor::Sf12, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf11, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf10, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf9, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf8, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf7, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf7, 250_000)),
+        Some(DataRate::fsk(50_000)),
+    ];
+
+    const MAX_TX_DATA_RATE: usize = 7;
+
+    const MAX_EIRP: i8 = 12;
+
+    const TX_POWERS: &'static [u8] = &[0, 2, 4, 6, 8, 10];
+
+    const MAX_PAYLOAD_SIZES: &'static [u8] = &[59, 59, 59, 123, 250, 250, 250, 250];
+
+    const RX1_DR_OFFSETS: &'static [&'static [usize]] = &[
+        &[0, 0, 0, 0, 0, 0],
+        &[1, 0, 0, 0, 0, 0],
+        &[2, 1, 0, 0, 0, 0],
+        &[3, 2, 1, 0, 0, 0],
+        &[4, 3, 2, 1, 0, 0],
+        &[5, 4, 3, 2, 1, 0],
+        &[6, 5, 4, 3, 2, 1],
+        &[7, 6, 5, 4, 3, 2],
+    ];
+
+    const BEACON: BeaconParameters<'static> =
+        BeaconParameters::new(3, &[434_665_000], &[434_665_000], 2, 0);
+
+    const DUTY_CYCLE_BANDS: &'static [DutyCycleBand] =
+        &[DutyCycleBand::new(433_050_000, 434_790_000, 100)];
+
+    const CF_LIST_TYPE: CfListType = CfListType::Frequencies;
+
+    /// The default channels only support DR0 to DR5, so DR6 and DR7 need channels that the network
+    /// adds.
+    fn supports_data_rate(_channel: usize, dr: usize) -> bool {
+        dr <= 5
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::radio::region::tests::{
+        assert_answers_on_uplink_channel, assert_default_channels_up_to_dr5,
+    };
+    use crate::radio::RegionalParameters;
+
+    #[test]
+    fn answers_on_uplink_channel() {
+        assert_answers_on_uplink_channel(
+            &EU433,
+            &[433_175_000, 433_375_000, 433_575_000],
+            (434_665_000, 0),
+            &[434_665_000],
+        );
+    }
+
+    #[test]
+    fn limits_duty_cycle_to_one_percent() {
+        let bands = EU433.duty_cycle_bands();
+        assert_eq!(bands.len(), 1);
+        assert!(bands[0].contains(433_175_000) && bands[0].contains(434_665_000));
+        assert_eq!(bands[0].duty_cycle(), 100);
+    }
+
+    #[test]
+    fn limits_default_channels_to_dr5() {
+        assert_default_channels_up_to_dr5(&EU433, &[6, 7]);
+    }
+}
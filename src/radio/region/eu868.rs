@@ -1,8 +1,10 @@
 use radio::modulation::lora::SpreadingFactor;
 
-use crate::radio::{DataRate, Hz, Region};
+use crate::radio::{
+    BeaconParameters, CfListType, DataRate, DutyCycleBand, Hz, LrFhssCodingRate, Region,
+};
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct EU868;
 
@@ -15,13 +17,90 @@ impl Region for EU868 {
 
     const RX2_FREQUENCY: Hz = 869_525_000;
 
-    const DATA_RATES: &'static [DataRate<Self>] = &[
-        DataRate::new(SpreadingFactor::Sf12, 125_000),
-        DataRate::new(SpreadingFactor::Sf11, 125_000),
-        DataRate::new(SpreadingFactor::Sf10, 125_000),
-        DataRate::new(SpreadingFactor::Sf9, 125_000),
-        DataRate::new(SpreadingFactor::Sf8, 125_000),
-        DataRate::new(SpreadingFactor::Sf7, 125_000),
-        DataRate::new(SpreadingFactor::Sf7, 250_000),
+    const RX2_DATA_RATE: usize = 0;
+
+    const DATA_RATES: &'static [Option<DataRate>] = &[
+        Some(DataRate::new(SpreadingFactor::Sf12, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf11, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf10, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf9, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf8, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf7, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf7, 250_000)),
+        Some(DataRate::fsk(50_000)),
+        Some(DataRate::lr_fhss(LrFhssCodingRate::Cr1_3, 137_000, 3_900)),
+        Some(DataRate::lr_fhss(LrFhssCodingRate::Cr2_3, 137_000, 3_900)),
+        Some(DataRate::lr_fhss(LrFhssCodingRate::Cr1_3, 336_000, 3_900)),
+        Some(DataRate::lr_fhss(LrFhssCodingRate::Cr2_3, 336_000, 3_900)),
     ];
+
+    const MAX_TX_DATA_RATE: usize = 11;
+
+    const MAX_EIRP: i8 = 16;
+
+    const TX_POWERS: &'static [u8] = &[0, 2, 4, 6, 8, 10, 12, 14];
+
+    const MAX_PAYLOAD_SIZES: &'static [u8] =
+        &[59, 59, 59, 123, 250, 250, 250, 250, 58, 123, 58, 123];
+
+    const RX1_DR_OFFSETS: &'static [&'static [usize]] = &[
+        &[0, 0, 0, 0, 0, 0],
+        &[1, 0, 0, 0, 0, 0],
+        &[2, 1, 0, 0, 0, 0],
+        &[3, 2, 1, 0, 0, 0],
+        &[4, 3, 2, 1, 0, 0],
+        &[5, 4, 3, 2, 1, 0],
+        &[6, 5, 4, 3, 2, 1],
+        &[7, 6, 5, 4, 3, 2],
+        &[1, 0, 0, 0, 0, 0],
+        &[2, 1, 0, 0, 0, 0],
+        &[1, 0, 0, 0, 0, 0],
+        &[2, 1, 0, 0, 0, 0],
+    ];
+
+    const BEACON: BeaconParameters<'static> =
+        BeaconParameters::new(3, &[869_525_000], &[869_525_000], 2, 0);
+
+    const DUTY_CYCLE_BANDS: &'static [DutyCycleBand] = &[
+        DutyCycleBand::new(863_000_000, 865_000_000, 1000),
+        DutyCycleBand::new(865_000_000, 868_000_000, 100),
+        DutyCycleBand::new(868_000_000, 868_600_000, 100),
+        DutyCycleBand::new(868_700_000, 869_200_000, 1000),
+        DutyCycleBand::new(869_400_000, 869_650_000, 10),
+        DutyCycleBand::new(869_700_000, 870_000_000, 100),
+    ];
+
+    const CF_LIST_TYPE: CfListType = CfListType::Frequencies;
+
+    /// The default channels only support the LoRa data rates of 125 kHz. FSK, LoRa at 250 kHz and
+    /// LR-FHSS require channels that the network adds.
+    fn supports_data_rate(_channel: usize, dr: usize) -> bool {
+        dr <= 5
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::radio::region::tests::assert_default_channels_up_to_dr5;
+    use crate::radio::{ChannelConfig, RegionalParameters};
+
+    #[test]
+    fn limits_default_channels_to_dr5() {
+        assert_default_channels_up_to_dr5(&EU868, &[6, 7, 8, 9, 10, 11]);
+    }
+
+    #[test]
+    fn converts_fsk_and_lr_fhss_channels() {
+        let channel = EU868.tx_channel(7, 1);
+        assert!(matches!(channel, Some(ChannelConfig::Fsk(fsk)) if fsk.freq_khz == 868_300));
+
+        let channel = EU868.tx_channel(8, 2);
+        assert!(
+            matches!(channel, Some(ChannelConfig::LrFhss(lr_fhss)) if lr_fhss.freq_khz == 868_500)
+        );
+        assert_eq!(EU868.rx1_channel(8, 2), None);
+        assert_eq!(EU868.rx1_data_rate(8, 0, false), Some(1));
+        assert_eq!(EU868.rx1_data_rate(11, 1, false), Some(1));
+    }
 }
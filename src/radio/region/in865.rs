@@ -0,0 +1,106 @@
+use radio::modulation::lora::SpreadingFactor;
+
+use crate::radio::{BeaconParameters, CfListType, DataRate, DutyCycleBand, Hz, Region};
+
+/// The IN865-867 region. The network may also raise the data rate in RX1, with RX1 data rate
+/// offsets 6 and 7.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct IN865;
+
+impl Region for IN865 {
+    const JOIN_FREQUENCIES: &'static [Hz] = &[865_062_500, 865_402_500, 865_985_000];
+
+    const TX_FREQUENCIES: &'static [Hz] = Self::JOIN_FREQUENCIES;
+
+    const RX1_FREQUENCIES: &'static [Hz] = Self::TX_FREQUENCIES;
+
+    const RX2_FREQUENCY: Hz = 866_550_000;
+
+    const RX2_DATA_RATE: usize = 2;
+
+    const DATA_RATES: &'static [Option<DataRate>] = &[
+        Some(DataRate::new(SpreadingFactor::Sf12, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf11, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf10, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf9, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf8, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf7, 125_000)),
+        None,
+        Some(DataRate::fsk(50_000)),
+    ];
+
+    const MAX_TX_DATA_RATE: usize = 7;
+
+    const MAX_EIRP: i8 = 30;
+
+    const TX_POWERS: &'static [u8] = &[0, 2, 4, 6, 8, 10, 12, 14, 16, 18, 20];
+
+    const MAX_PAYLOAD_SIZES: &'static [u8] = &[59, 59, 59, 123, 250, 250, 0, 250];
+
+    // Offsets 6 and 7 raise the data rate by one and two respectively, where DR5 is raised to the
+    // FSK DR7.
+    const RX1_DR_OFFSETS: &'static [&'static [usize]] = &[
+        &[0, 0, 0, 0, 0, 0, 1, 2],
+        &[1, 0, 0, 0, 0, 0, 2, 3],
+        &[2, 1, 0, 0, 0, 0, 3, 4],
+        &[3, 2, 1, 0, 0, 0, 4, 5],
+        &[4, 3, 2, 1, 0, 0, 5, 5],
+        &[5, 4, 3, 2, 1, 0, 5, 7],
+        &[],
+        &[7, 5, 5, 4, 3, 2, 7, 7],
+    ];
+
+    const BEACON: BeaconParameters<'static> =
+        BeaconParameters::new(4, &[866_550_000], &[866_550_000], 1, 0);
+
+    const DUTY_CYCLE_BANDS: &'static [DutyCycleBand] = &[];
+
+    const CF_LIST_TYPE: CfListType = CfListType::Frequencies;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::radio::region::tests::assert_answers_on_uplink_channel;
+    use crate::radio::{ChannelConfig, RegionalParameters};
+
+    #[test]
+    fn selects_fsk_channel() {
+        let mask = <IN865 as Region>::default_channel_mask();
+        assert_eq!(IN865.select_channel(6, &mask, 0), None);
+        assert_eq!(IN865.select_channel(7, &mask, 2), Some(2));
+        assert!(matches!(
+            IN865.tx_channel(7, 2),
+            Some(ChannelConfig::Fsk(_))
+        ));
+    }
+
+    #[test]
+    fn raises_rx1_data_rate_to_fsk() {
+        assert_eq!(IN865.rx1_data_rate(5, 7, false), Some(7));
+        assert_eq!(IN865.rx1_data_rate(7, 1, false), Some(5));
+    }
+
+    #[test]
+    fn reserves_dr6() {
+        assert_eq!(IN865.data_rate(6), None);
+        assert_eq!(IN865.max_mac_payload_size(6, false), 0);
+        assert_eq!(IN865.rx1_data_rate(6, 0, false), None);
+        let sizes = [(0, 59), (3, 123), (5, 250), (7, 250)];
+        for (dr, m) in sizes {
+            assert_eq!(IN865.max_mac_payload_size(dr, false), m, "DR{}", dr);
+        }
+    }
+
+    #[test]
+    fn answers_on_uplink_channel() {
+        assert_answers_on_uplink_channel(
+            &IN865,
+            &[865_062_500, 865_402_500, 865_985_000],
+            (866_550_000, 2),
+            &[866_550_000],
+        );
+        assert_eq!(IN865.beacon().data_rate(), 4);
+    }
+}
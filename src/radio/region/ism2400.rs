@@ -0,0 +1,126 @@
+use radio::modulation::lora::{CodingRate, SpreadingFactor};
+
+use crate::radio::{BeaconParameters, CfListType, DataRate, DutyCycleBand, Hz, Region};
+
+/// The worldwide 2.4 GHz ISM band. All data rates use a bandwidth of 812.5 kHz, which radios are
+/// configured with as 812 kHz, and a coding rate of 4/8 with long interleaving. Radios must
+/// support the latter through [RadioHooks::long_interleaving_channel], or transmitting fails with
+/// [RadioError::UnsupportedDataRate].
+///
+/// [RadioHooks::long_interleaving_channel]: crate::radio::RadioHooks::long_interleaving_channel
+/// [RadioError::UnsupportedDataRate]: crate::radio::RadioError::UnsupportedDataRate
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ISM2400;
+
+impl Region for ISM2400 {
+    const JOIN_FREQUENCIES: &'static [Hz] = &[2_403_000_000, 2_425_000_000, 2_479_000_000];
+
+    const TX_FREQUENCIES: &'static [Hz] = Self::JOIN_FREQUENCIES;
+
+    const RX1_FREQUENCIES: &'static [Hz] = Self::TX_FREQUENCIES;
+
+    const RX2_FREQUENCY: Hz = 2_423_000_000;
+
+    const RX2_DATA_RATE: usize = 0;
+
+    const DATA_RATES: &'static [Option<DataRate>] = &[
+        Some(DataRate::new(SpreadingFactor::Sf12, 812_500)),
+        Some(DataRate::new(SpreadingFactor::Sf11, 812_500)),
+        Some(DataRate::new(SpreadingFactor::Sf10, 812_500)),
+        Some(DataRate::new(SpreadingFactor::Sf9, 812_500)),
+        Some(DataRate::new(SpreadingFactor::Sf8, 812_500)),
+        Some(DataRate::new(SpreadingFactor::Sf7, 812_500)),
+        Some(DataRate::new(SpreadingFactor::Sf6, 812_500)),
+        Some(DataRate::new(SpreadingFactor::Sf5, 812_500)),
+    ];
+
+    const CODING_RATE: CodingRate = CodingRate::Cr4_8;
+
+    const LONG_INTERLEAVING: bool = true;
+
+    const MAX_TX_DATA_RATE: usize = 7;
+
+    const MAX_EIRP: i8 = 10;
+
+    const TX_POWERS: &'static [u8] = &[0, 2, 4, 6, 8, 10, 12, 14];
+
+    const MAX_PAYLOAD_SIZES: &'static [u8] = &[59, 123, 248, 248, 248, 248, 248, 248];
+
+    const RX1_DR_OFFSETS: &'static [&'static [usize]] = &[
+        &[0, 0, 0, 0, 0, 0],
+        &[1, 0, 0, 0, 0, 0],
+        &[2, 1, 0, 0, 0, 0],
+        &[3, 2, 1, 0, 0, 0],
+        &[4, 3, 2, 1, 0, 0],
+        &[5, 4, 3, 2, 1, 0],
+        &[6, 5, 4, 3, 2, 1],
+        &[7, 6, 5, 4, 3, 2],
+    ];
+
+    const BEACON: BeaconParameters<'static> =
+        BeaconParameters::new(3, &[2_424_000_000], &[2_424_000_000], 2, 0);
+
+    const DUTY_CYCLE_BANDS: &'static [DutyCycleBand] = &[];
+
+    const CF_LIST_TYPE: CfListType = CfListType::Frequencies;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::radio::{ChannelConfig, RegionalParameters};
+
+    #[test]
+    fn uses_long_interleaving() {
+        let channel = ISM2400.tx_channel(7, 0);
+        assert!(matches!(
+            channel,
+            Some(ChannelConfig::LongInterleaving(lora))
+                if lora.freq_khz == 2_403_000 && lora.bw_khz == 812 && lora.cr == CodingRate::Cr4_8
+        ));
+        assert!(matches!(
+            ISM2400.rx2_channel(0),
+            Some(ChannelConfig::LongInterleaving(_))
+        ));
+    }
+
+    #[test]
+    fn restores_bandwidth_of_channels() {
+        for dr in 0..=7 {
+            let channel = ISM2400.tx_channel(dr, 1).unwrap();
+            assert_eq!(
+                Some(&channel.data_rate()),
+                ISM2400.data_rate(dr),
+                "DR{}",
+                dr
+            );
+        }
+    }
+
+    #[test]
+    fn has_channels_across_the_band() {
+        let channels = [2_403_000_000, 2_425_000_000, 2_479_000_000];
+        for (channel, frequency) in channels.into_iter().enumerate() {
+            assert_eq!(ISM2400.tx_frequency(channel), frequency);
+            assert_eq!(ISM2400.rx1_frequency(channel), frequency);
+        }
+        assert_eq!(
+            (ISM2400.rx2_frequency(), ISM2400.rx2_data_rate()),
+            (2_423_000_000, 0)
+        );
+        assert_eq!(ISM2400.beacon().frequencies(), &[2_424_000_000]);
+    }
+
+    #[test]
+    fn limits_payload_sizes() {
+        let sizes = [(0, 59), (1, 123), (2, 248), (7, 248)];
+        for (dr, m) in sizes {
+            assert_eq!(ISM2400.max_mac_payload_size(dr, false), m, "DR{}", dr);
+        }
+        assert_eq!(
+            ISM2400.data_rate(7),
+            Some(&DataRate::new(SpreadingFactor::Sf5, 812_500))
+        );
+    }
+}
@@ -0,0 +1,104 @@
+use core::time::Duration;
+
+use radio::modulation::lora::SpreadingFactor;
+
+use crate::radio::{
+    BeaconParameters, CfListType, DataRate, DutyCycleBand, Hz, ListenBeforeTalk, Region,
+};
+
+/// The KR920-923 region. Devices must listen before they talk on every channel.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct KR920;
+
+impl Region for KR920 {
+    const JOIN_FREQUENCIES: &'static [Hz] = &[922_100_000, 922_300_000, 922_500_000];
+
+    const TX_FREQUENCIES: &'static [Hz] = Self::JOIN_FREQUENCIES;
+
+    const RX1_FREQUENCIES: &'static [Hz] = Self::TX_FREQUENCIES;
+
+    const RX2_FREQUENCY: Hz = 921_900_000;
+
+    const RX2_DATA_RATE: usize = 0;
+
+    const DATA_RATES: &'static [Option<DataRate>] = &[
+        Some(DataRate::new(SpreadingFactor::Sf12, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf11, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf10, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf9, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf8, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf7, 125_000)),
+    ];
+
+    const MAX_TX_DATA_RATE: usize = 5;
+
+    // The default for channels above 922 MHz
+    const MAX_EIRP: i8 = 14;
+
+    const TX_POWERS: &'static [u8] = &[0, 2, 4, 6, 8, 10, 12, 14];
+
+    const MAX_PAYLOAD_SIZES: &'static [u8] = &[59, 59, 59, 123, 250, 250];
+
+    const RX1_DR_OFFSETS: &'static [&'static [usize]] = &[
+        &[0, 0, 0, 0, 0, 0],
+        &[1, 0, 0, 0, 0, 0],
+        &[2, 1, 0, 0, 0, 0],
+        &[3, 2, 1, 0, 0, 0],
+        &[4, 3, 2, 1, 0, 0],
+        &[5, 4, 3, 2, 1, 0],
+    ];
+
+    const BEACON: BeaconParameters<'static> =
+        BeaconParameters::new(3, &[923_100_000], &[923_100_000], 2, 0);
+
+    const DUTY_CYCLE_BANDS: &'static [DutyCycleBand] = &[];
+
+    const CF_LIST_TYPE: CfListType = CfListType::Frequencies;
+
+    const LISTEN_BEFORE_TALK: Option<ListenBeforeTalk> =
+        Some(ListenBeforeTalk::new(-65, Duration::from_millis(5)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::radio::region::tests::{assert_answers_on_uplink_channel, assert_rx1_data_rates};
+    use crate::radio::RegionalParameters;
+
+    #[test]
+    fn listens_before_talking() {
+        let lbt = KR920.listen_before_talk().unwrap();
+        assert_eq!(lbt.threshold(), -65);
+        assert_eq!(KR920.max_eirp(), 14);
+    }
+
+    #[test]
+    fn answers_on_uplink_channel() {
+        assert_answers_on_uplink_channel(
+            &KR920,
+            &[922_100_000, 922_300_000, 922_500_000],
+            (921_900_000, 0),
+            &[923_100_000],
+        );
+    }
+
+    #[test]
+    fn has_six_data_rates() {
+        assert_eq!(KR920.data_rate(6), None);
+        let sizes = [(0, 59), (2, 59), (3, 123), (5, 250), (6, 0)];
+        for (dr, m) in sizes {
+            assert_eq!(KR920.max_mac_payload_size(dr, false), m, "DR{}", dr);
+        }
+        assert_rx1_data_rates(
+            &KR920,
+            false,
+            &[
+                (0, 0, Some(0)),
+                (3, 1, Some(2)),
+                (5, 5, Some(0)),
+                (6, 0, None),
+            ],
+        );
+    }
+}
@@ -0,0 +1,260 @@
+use crate::radio::DynamicRegion;
+
+/// An area from `min` to `max` latitude and longitude in degrees, and the region that applies in
+/// it.
+struct Area {
+    latitude: (f32, f32),
+    longitude: (f32, f32),
+    region: DynamicRegion,
+}
+
+const fn area(latitude: (f32, f32), longitude: (f32, f32), region: DynamicRegion) -> Area {
+    Area {
+        latitude,
+        longitude,
+        region,
+    }
+}
+
+/// Coarse bounding boxes of countries and continents. Smaller areas come first, so they take
+/// precedence over the larger areas that contain them.
+const AREAS: &[Area] = &[
+    // Asia
+    area((1.1, 1.5), (103.6, 104.1), DynamicRegion::AS923_1), // Singapore
+    // Hong Kong, in two parts that leave out Shenzhen to the north
+    area((22.15, 22.47), (113.83, 114.05), DynamicRegion::AS923_1),
+    area((22.15, 22.51), (114.05, 114.45), DynamicRegion::AS923_1),
+    area((21.5, 25.5), (119.5, 122.5), DynamicRegion::AS923_1), // Taiwan
+    area((29.5, 33.4), (34.2, 35.9), DynamicRegion::AS923_4),   // Israel
+    area((34.05, 34.75), (129.15, 129.55), DynamicRegion::AS923_1JP), // Tsushima
+    area((34.0, 38.7), (124.5, 129.6), DynamicRegion::KR920),   // South Korea
+    area((33.0, 34.0), (126.0, 127.0), DynamicRegion::KR920),   // Jeju
+    area((37.2, 37.6), (130.7, 132.0), DynamicRegion::KR920),   // Ulleungdo and Dokdo
+    area((30.0, 41.6), (129.6, 142.2), DynamicRegion::AS923_1JP), // Honshu, Shikoku and Kyushu
+    area((41.3, 45.6), (139.3, 146.0), DynamicRegion::AS923_1JP), // Hokkaido
+    area((24.0, 30.0), (122.9, 131.5), DynamicRegion::AS923_1JP), // Ryukyu Islands
+    area((4.5, 21.5), (116.0, 127.0), DynamicRegion::AS923_3),  // Philippines
+    // Vietnam, in parts that follow its borders with Laos and Cambodia, and leave out Guangxi and
+    // Hainan
+    area((20.5, 23.4), (104.3, 108.0), DynamicRegion::AS923_2), // Red River Delta and the north
+    area((21.0, 22.8), (103.0, 104.3), DynamicRegion::AS923_2), // Dien Bien and Lai Chau
+    area((18.5, 20.5), (105.0, 107.0), DynamicRegion::AS923_2), // Thanh Hoa and Nghe An
+    area((17.0, 18.5), (106.0, 107.2), DynamicRegion::AS923_2), // Ha Tinh and Quang Binh
+    area((15.0, 17.0), (107.3, 109.0), DynamicRegion::AS923_2), // Hue and Da Nang
+    area((10.5, 15.0), (107.6, 109.5), DynamicRegion::AS923_2), // Central Highlands and coast
+    area((10.9, 11.6), (106.15, 107.6), DynamicRegion::AS923_2), // Binh Duong and Dong Nai
+    area((10.4, 10.9), (105.0, 107.6), DynamicRegion::AS923_2), // Ho Chi Minh City
+    area((8.4, 10.4), (103.8, 107.6), DynamicRegion::AS923_2),  // Mekong Delta
+    // Thailand, Laos and Cambodia, after Vietnam, which takes the eastern edge of this area
+    area((5.5, 21.0), (97.3, 107.7), DynamicRegion::AS923_1),
+    area((21.0, 22.4), (101.8, 102.7), DynamicRegion::AS923_1), // Phongsaly
+    area((0.8, 7.5), (99.5, 119.5), DynamicRegion::AS923_1),    // Malaysia
+    area((-11.0, 6.0), (95.0, 141.0), DynamicRegion::AS923_2),  // Indonesia
+    // India, in parts that leave out Pakistan, Nepal, Bhutan, Bangladesh and Sri Lanka
+    area((8.0, 10.0), (68.0, 79.5), DynamicRegion::IN865), // Southern tip
+    area((10.0, 24.0), (68.0, 88.7), DynamicRegion::IN865), // Peninsula
+    area((24.0, 28.0), (71.2, 80.0), DynamicRegion::IN865), // Rajasthan
+    area((28.0, 32.5), (74.6, 80.0), DynamicRegion::IN865), // Delhi and Punjab
+    area((24.0, 27.3), (80.0, 84.5), DynamicRegion::IN865), // Uttar Pradesh
+    area((24.0, 26.4), (84.5, 88.2), DynamicRegion::IN865), // Bihar
+    area((25.2, 26.7), (89.8, 96.0), DynamicRegion::IN865), // Assam and Meghalaya
+    // China, in parts that leave out its western and northern neighbours
+    area((18.0, 42.0), (97.5, 124.3), DynamicRegion::CN470_20A), // Eastern China
+    area((42.0, 53.5), (119.0, 130.5), DynamicRegion::CN470_20A), // Northeastern China
+    area((29.0, 36.0), (80.5, 97.5), DynamicRegion::CN470_20A),  // Tibet and Qinghai
+    area((35.5, 45.0), (80.0, 96.0), DynamicRegion::CN470_20A),  // Xinjiang
+    // Oceania
+    area((-48.0, -34.0), (166.0, 179.0), DynamicRegion::AU915), // New Zealand
+    area((-44.0, -10.0), (112.0, 154.0), DynamicRegion::AU915), // Australia
+    // Americas
+    area((14.0, 33.0), (-118.0, -86.0), DynamicRegion::US915), // Mexico
+    area((24.0, 72.0), (-170.0, -52.0), DynamicRegion::US915), // United States and Canada
+    area((18.5, 22.5), (-160.5, -154.5), DynamicRegion::US915), // Hawaii
+    area((-56.0, 13.0), (-82.0, -34.0), DynamicRegion::AU915), // South America
+    // Europe, Africa and the Middle East
+    area((36.0, 42.0), (26.0, 45.0), DynamicRegion::EU868), // Turkey
+    area((54.4, 55.3), (19.6, 22.8), DynamicRegion::RU864), // Kaliningrad
+    area((34.0, 72.0), (-25.0, 30.0), DynamicRegion::EU868), // Europe
+    // Ukraine east of the area of Europe, in parts that follow its border with Russia
+    area((44.4, 52.4), (30.0, 34.0), DynamicRegion::EU868), // Kyiv, Chernihiv and Odesa
+    area((44.4, 51.3), (34.0, 36.0), DynamicRegion::EU868), // Sumy, Poltava and Crimea
+    area((46.0, 50.1), (36.0, 38.2), DynamicRegion::EU868), // Kharkiv and Donetsk
+    area((47.8, 50.0), (38.2, 40.2), DynamicRegion::EU868), // Luhansk
+    area((41.0, 82.0), (30.0, 180.0), DynamicRegion::RU864), // Russia
+    area((-35.0, 37.0), (-18.0, 52.0), DynamicRegion::EU868), // Africa
+    area((12.0, 37.0), (34.0, 60.0), DynamicRegion::EU868), // Middle East
+];
+
+/// Returns the region that applies at `latitude` and `longitude` in degrees, or `None` if the
+/// position is not covered, e.g. at sea. The areas are coarse, so the result may be wrong close to
+/// borders.
+pub fn region_at(latitude: f32, longitude: f32) -> Option<DynamicRegion> {
+    AREAS
+        .iter()
+        .find(|area| {
+            (area.latitude.0..=area.latitude.1).contains(&latitude)
+                && (area.longitude.0..=area.longitude.1).contains(&longitude)
+        })
+        .map(|area| area.region)
+}
+
+/// Keeps track of the region a moving device is in. A new region only takes effect after a number
+/// of consecutive positions in it, so that a device travelling along a border, or receiving an
+/// inaccurate position, does not keep switching regions.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RegionSelector {
+    region: Option<DynamicRegion>,
+    candidate: Option<DynamicRegion>,
+    count: u8,
+    confirmations: u8,
+}
+
+impl RegionSelector {
+    /// Creates a selector that switches regions after `confirmations` consecutive positions in
+    /// the new region. The first region is taken immediately.
+    pub fn new(confirmations: u8) -> Self {
+        RegionSelector {
+            region: None,
+            candidate: None,
+            count: 0,
+            confirmations,
+        }
+    }
+
+    /// The region the device is in, if known.
+    pub fn region(&self) -> Option<DynamicRegion> {
+        self.region
+    }
+
+    /// Processes a new position. Returns the new region if the device should switch to it, in
+    /// which case it must join again before transmitting.
+    pub fn update(&mut self, latitude: f32, longitude: f32) -> Option<DynamicRegion> {
+        let region = region_at(latitude, longitude)?;
+        if self.region.is_none() {
+            self.region = Some(region);
+            return self.region;
+        }
+        if self.region == Some(region) {
+            self.candidate = None;
+            self.count = 0;
+            return None;
+        }
+
+        if self.candidate == Some(region) {
+            self.count += 1;
+        } else {
+            self.candidate = Some(region);
+            self.count = 1;
+        }
+
+        if self.count >= self.confirmations {
+            self.region = self.candidate.take();
+            self.count = 0;
+            self.region
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Cities on either side of the borders between areas, and the region they are in.
+    const CITIES: &[(&str, f32, f32, Option<DynamicRegion>)] = &[
+        ("Seoul", 37.57, 126.98, Some(DynamicRegion::KR920)),
+        ("Busan", 35.18, 129.08, Some(DynamicRegion::KR920)),
+        ("Jeju", 33.5, 126.53, Some(DynamicRegion::KR920)),
+        ("Fukuoka", 33.59, 130.4, Some(DynamicRegion::AS923_1JP)),
+        ("Nagasaki", 32.75, 129.87, Some(DynamicRegion::AS923_1JP)),
+        ("Tsushima", 34.2, 129.29, Some(DynamicRegion::AS923_1JP)),
+        ("Tokyo", 35.68, 139.69, Some(DynamicRegion::AS923_1JP)),
+        ("Sapporo", 43.06, 141.35, Some(DynamicRegion::AS923_1JP)),
+        ("Naha", 26.21, 127.68, Some(DynamicRegion::AS923_1JP)),
+        ("Vladivostok", 43.12, 131.89, Some(DynamicRegion::RU864)),
+        ("Hong Kong", 22.28, 114.16, Some(DynamicRegion::AS923_1)),
+        ("Tuen Mun", 22.39, 113.97, Some(DynamicRegion::AS923_1)),
+        ("Shenzhen", 22.54, 114.06, Some(DynamicRegion::CN470_20A)),
+        ("Shekou", 22.48, 113.92, Some(DynamicRegion::CN470_20A)),
+        ("Harbin", 45.75, 126.65, Some(DynamicRegion::CN470_20A)),
+        ("Lhasa", 29.65, 91.1, Some(DynamicRegion::CN470_20A)),
+        ("Urumqi", 43.83, 87.62, Some(DynamicRegion::CN470_20A)),
+        ("Delhi", 28.61, 77.21, Some(DynamicRegion::IN865)),
+        ("Amritsar", 31.63, 74.87, Some(DynamicRegion::IN865)),
+        ("Mumbai", 19.08, 72.88, Some(DynamicRegion::IN865)),
+        ("Kolkata", 22.57, 88.36, Some(DynamicRegion::IN865)),
+        ("Patna", 25.59, 85.14, Some(DynamicRegion::IN865)),
+        ("Guwahati", 26.14, 91.74, Some(DynamicRegion::IN865)),
+        ("Rameswaram", 9.29, 79.31, Some(DynamicRegion::IN865)),
+        ("Chennai", 13.08, 80.27, Some(DynamicRegion::IN865)),
+        ("Jaffna", 9.66, 80.0, None),
+        ("Hanoi", 21.03, 105.85, Some(DynamicRegion::AS923_2)),
+        ("Dien Bien Phu", 21.39, 103.02, Some(DynamicRegion::AS923_2)),
+        ("Vinh", 18.67, 105.68, Some(DynamicRegion::AS923_2)),
+        ("Hue", 16.46, 107.59, Some(DynamicRegion::AS923_2)),
+        ("Da Nang", 16.05, 108.2, Some(DynamicRegion::AS923_2)),
+        ("Buon Ma Thuot", 12.67, 108.04, Some(DynamicRegion::AS923_2)),
+        (
+            "Ho Chi Minh City",
+            10.78,
+            106.7,
+            Some(DynamicRegion::AS923_2),
+        ),
+        ("Can Tho", 10.03, 105.78, Some(DynamicRegion::AS923_2)),
+        ("Khon Kaen", 16.43, 102.83, Some(DynamicRegion::AS923_1)),
+        ("Udon Thani", 17.4, 102.8, Some(DynamicRegion::AS923_1)),
+        ("Vientiane", 17.97, 102.6, Some(DynamicRegion::AS923_1)),
+        ("Luang Prabang", 19.89, 102.13, Some(DynamicRegion::AS923_1)),
+        ("Phongsaly", 21.68, 102.1, Some(DynamicRegion::AS923_1)),
+        ("Pakse", 15.12, 105.8, Some(DynamicRegion::AS923_1)),
+        ("Phnom Penh", 11.55, 104.9, Some(DynamicRegion::AS923_1)),
+        ("Bangkok", 13.76, 100.5, Some(DynamicRegion::AS923_1)),
+        ("Nanning", 22.8, 108.3, Some(DynamicRegion::CN470_20A)),
+        ("Sanya", 18.25, 109.5, Some(DynamicRegion::CN470_20A)),
+        ("Lahore", 31.55, 74.34, None),
+        ("Karachi", 24.86, 67.0, None),
+        ("Kathmandu", 27.7, 85.32, None),
+        ("Dhaka", 23.81, 90.41, None),
+        ("Singapore", 1.35, 103.82, Some(DynamicRegion::AS923_1)),
+        ("Tel Aviv", 32.09, 34.78, Some(DynamicRegion::AS923_4)),
+        ("Amsterdam", 52.37, 4.9, Some(DynamicRegion::EU868)),
+        ("Gdansk", 54.35, 18.65, Some(DynamicRegion::EU868)),
+        ("Klaipeda", 55.7, 21.14, Some(DynamicRegion::EU868)),
+        ("Kaliningrad", 54.71, 20.51, Some(DynamicRegion::RU864)),
+        ("Kyiv", 50.45, 30.52, Some(DynamicRegion::EU868)),
+        ("Sumy", 50.91, 34.8, Some(DynamicRegion::EU868)),
+        ("Kharkiv", 49.99, 36.23, Some(DynamicRegion::EU868)),
+        ("Mariupol", 47.1, 37.55, Some(DynamicRegion::EU868)),
+        ("Luhansk", 48.57, 39.31, Some(DynamicRegion::EU868)),
+        ("Belgorod", 50.6, 36.59, Some(DynamicRegion::RU864)),
+        ("Voronezh", 51.67, 39.18, Some(DynamicRegion::RU864)),
+        ("Rostov-on-Don", 47.23, 39.72, Some(DynamicRegion::RU864)),
+        ("Krasnodar", 45.04, 38.98, Some(DynamicRegion::RU864)),
+        ("New York", 40.71, -74.01, Some(DynamicRegion::US915)),
+        ("Honolulu", 21.3, -157.8, Some(DynamicRegion::US915)),
+        ("Sydney", -33.87, 151.21, Some(DynamicRegion::AU915)),
+        ("Atlantic Ocean", 30.0, -40.0, None),
+    ];
+
+    #[test]
+    fn finds_region_of_cities() {
+        for &(city, latitude, longitude, region) in CITIES {
+            assert_eq!(region_at(latitude, longitude), region, "{}", city);
+        }
+    }
+
+    #[test]
+    fn switches_region_after_confirmations() {
+        let mut selector = RegionSelector::new(2);
+        assert_eq!(selector.update(52.37, 4.9), Some(DynamicRegion::EU868));
+        assert_eq!(selector.update(40.71, -74.01), None);
+        assert_eq!(selector.update(52.37, 4.9), None);
+        assert_eq!(selector.update(40.71, -74.01), None);
+        assert_eq!(selector.update(40.71, -74.01), Some(DynamicRegion::US915));
+        assert_eq!(selector.region(), Some(DynamicRegion::US915));
+        assert_eq!(selector.update(30.0, -40.0), None);
+        assert_eq!(selector.region(), Some(DynamicRegion::US915));
+    }
+}
@@ -1,22 +1,860 @@
+use core::time::Duration;
+
+use radio::modulation::lora::CodingRate;
+
+use crate::lorawan::BEACON_PERIOD;
+pub use crate::radio::region::as923::{AS923, AS923_1, AS923_1JP, AS923_2, AS923_3, AS923_4};
+pub use crate::radio::region::au915::AU915;
+pub use crate::radio::region::cn470::{CN470, CN470_20A, CN470_20B, CN470_26A, CN470_26B};
+pub use crate::radio::region::cn779::CN779;
+pub use crate::radio::region::custom::{ChannelPlan, ChannelPlanError, CustomRegion};
+pub use crate::radio::region::dynamic::DynamicRegion;
+pub use crate::radio::region::eu433::EU433;
 pub use crate::radio::region::eu868::EU868;
-use crate::radio::{DataRate, Hz, RadioError};
+pub use crate::radio::region::in865::IN865;
+pub use crate::radio::region::ism2400::ISM2400;
+pub use crate::radio::region::kr920::KR920;
+pub use crate::radio::region::location::{region_at, RegionSelector};
+pub use crate::radio::region::ru864::RU864;
+pub use crate::radio::region::us915::US915;
+use crate::radio::{ChannelConfig, ChannelMask, DataRate, Hz, SubBand};
 
+mod as923;
+mod au915;
+mod cn470;
+mod cn779;
+mod custom;
+mod dynamic;
+mod eu433;
 mod eu868;
+mod in865;
+mod ism2400;
+mod kr920;
+mod location;
+mod ru864;
+mod us915;
 
+/// Describes the regional parameters of a LoRaWAN region at compile time. Devices use these
+/// through [RegionalParameters], which every region implements.
+///
+/// Uplink channels are identified by their index. Most regions list their channel frequencies in
+/// [TX_FREQUENCIES] and [RX1_FREQUENCIES], but regions with large channel plans can leave these
+/// empty and compute the frequencies in [channel_count], [tx_frequency] and [rx1_frequency]
+/// instead.
+///
+/// [TX_FREQUENCIES]: Region::TX_FREQUENCIES
+/// [RX1_FREQUENCIES]: Region::RX1_FREQUENCIES
+/// [channel_count]: Region::channel_count
+/// [tx_frequency]: Region::tx_frequency
+/// [rx1_frequency]: Region::rx1_frequency
 pub trait Region: Sized + 'static {
-    const JOIN_FREQUENCIES: &'static [Hz];
+    const JOIN_FREQUENCIES: &'static [Hz] = &[];
 
-    const TX_FREQUENCIES: &'static [Hz];
+    const TX_FREQUENCIES: &'static [Hz] = &[];
 
-    const RX1_FREQUENCIES: &'static [Hz];
+    const RX1_FREQUENCIES: &'static [Hz] = &[];
 
     const RX2_FREQUENCY: Hz;
 
-    const DATA_RATES: &'static [DataRate<Self>];
+    /// The default data rate used in the RX2 window.
+    const RX2_DATA_RATE: usize;
+
+    /// The data rates of the region, indexed by DR. Reserved data rates are `None`.
+    const DATA_RATES: &'static [Option<DataRate>];
+
+    /// The coding rate used for all LoRa transmissions.
+    const CODING_RATE: CodingRate = CodingRate::Cr4_5;
+
+    /// Whether LoRa transmissions use long interleaving on top of [CODING_RATE]. Only radios that
+    /// support it through [RadioHooks::long_interleaving_channel] can use such regions.
+    ///
+    /// [CODING_RATE]: Region::CODING_RATE
+    /// [RadioHooks::long_interleaving_channel]: crate::radio::RadioHooks::long_interleaving_channel
+    const LONG_INTERLEAVING: bool = false;
+
+    /// The highest data rate a device may use for uplinks. [supports_data_rate] may limit
+    /// individual channels further.
+    ///
+    /// [supports_data_rate]: Region::supports_data_rate
+    const MAX_TX_DATA_RATE: usize;
+
+    /// The maximum EIRP in dBm a device may transmit with by default.
+    const MAX_EIRP: i8;
+
+    /// The attenuation in dB below [MAX_EIRP] for each TXPower index.
+    ///
+    /// [MAX_EIRP]: Region::MAX_EIRP
+    const TX_POWERS: &'static [u8];
+
+    /// The maximum MAC payload size (M) per data rate. A size of zero means the data rate cannot
+    /// be used.
+    const MAX_PAYLOAD_SIZES: &'static [u8];
+
+    /// The maximum MAC payload size (M) per data rate when the 400 ms dwell time limit applies.
+    const MAX_PAYLOAD_SIZES_DWELL: &'static [u8] = Self::MAX_PAYLOAD_SIZES;
+
+    /// Whether uplinks are limited to a dwell time of 400 ms by default.
+    const UPLINK_DWELL_TIME: bool = false;
+
+    /// Whether downlinks are limited to a dwell time of 400 ms by default.
+    const DOWNLINK_DWELL_TIME: bool = false;
+
+    /// Whether the network may change the dwell times and maximum EIRP with a TxParamSetupReq.
+    const TX_PARAM_SETUP: bool = false;
+
+    /// The data rate used for join-requests.
+    const JOIN_DATA_RATE: usize = 0;
+
+    /// The data rate to use for RX1, indexed first by the uplink data rate and then by the RX1 data
+    /// rate offset.
+    const RX1_DR_OFFSETS: &'static [&'static [usize]];
+
+    const BEACON: BeaconParameters<'static>;
+
+    /// The sub-bands that are subject to a duty-cycle limit. This is empty for regions that use
+    /// dwell time or listen-before-talk instead.
+    const DUTY_CYCLE_BANDS: &'static [DutyCycleBand];
+
+    /// How the optional CFList in a join-accept should be interpreted.
+    const CF_LIST_TYPE: CfListType;
+
+    /// Groups of uplink channels that gateways commonly listen on, which [join_scan] tries one
+    /// after another. This is empty for regions where all gateways listen on the default channels.
+    ///
+    /// [join_scan]: crate::device::Device::join_scan
+    const SUB_BANDS: &'static [ChannelMask] = &[];
+
+    /// Whether the device must check that a channel is free before transmitting on it.
+    const LISTEN_BEFORE_TALK: Option<ListenBeforeTalk> = None;
+
+    /// The number of uplink channels.
+    fn channel_count() -> usize {
+        Self::TX_FREQUENCIES.len()
+    }
+
+    /// Returns the frequency of uplink channel `channel`, which must be below [channel_count].
+    ///
+    /// [channel_count]: Region::channel_count
+    fn tx_frequency(channel: usize) -> Hz {
+        Self::TX_FREQUENCIES[channel]
+    }
+
+    /// Returns the RX1 frequency for an uplink on channel `channel`. Uplink channel `n` answers on
+    /// `RX1_FREQUENCIES[n % len]`, and regions with an empty [RX1_FREQUENCIES] answer on the
+    /// uplink frequency.
+    ///
+    /// [RX1_FREQUENCIES]: Region::RX1_FREQUENCIES
+    fn rx1_frequency(channel: usize) -> Hz {
+        match Self::RX1_FREQUENCIES.len() {
+            0 => Self::tx_frequency(channel),
+            len => Self::RX1_FREQUENCIES[channel % len],
+        }
+    }
+
+    /// Whether uplink channel `channel` may be used with data rate `dr`. By default, every channel
+    /// supports the data rates up to [MAX_TX_DATA_RATE] that are not reserved.
+    ///
+    /// [MAX_TX_DATA_RATE]: Region::MAX_TX_DATA_RATE
+    fn supports_data_rate(_channel: usize, dr: usize) -> bool {
+        dr <= Self::MAX_TX_DATA_RATE && matches!(Self::DATA_RATES.get(dr), Some(Some(_)))
+    }
+
+    /// The channels a device uses until the network configures them.
+    fn default_channel_mask() -> ChannelMask {
+        ChannelMask::first(Self::channel_count())
+    }
+
+    /// The channels a device sends join-requests on, unless told otherwise.
+    fn join_channel_mask() -> ChannelMask {
+        Self::default_channel_mask()
+    }
+
+    /// Returns the maximum MAC payload size (M) for data rate `dr`, or zero if it cannot be used.
+    fn max_mac_payload_size(dr: usize, dwell_time: bool) -> usize {
+        let sizes = if dwell_time {
+            Self::MAX_PAYLOAD_SIZES_DWELL
+        } else {
+            Self::MAX_PAYLOAD_SIZES
+        };
+        sizes.get(dr).copied().unwrap_or(0) as usize
+    }
+
+    /// Returns the data rate to listen on in RX1, given the data rate of the uplink, the RX1 data
+    /// rate offset configured by the network and whether the downlink dwell time applies.
+    fn rx1_data_rate(tx_dr: usize, rx1_dr_offset: usize, _dwell_time: bool) -> Option<usize> {
+        Self::RX1_DR_OFFSETS
+            .get(tx_dr)
+            .and_then(|offsets| offsets.get(rx1_dr_offset))
+            .copied()
+    }
+}
+
+/// The regional parameters of [Region], as an object-safe trait. This lets a device choose its
+/// region at runtime, e.g. through [DynamicRegion] or `&dyn RegionalParameters`, while regions
+/// known at compile time are still dispatched statically.
+///
+/// Every [Region] implements this trait, with its unit struct as the value.
+pub trait RegionalParameters {
+    fn channel_count(&self) -> usize;
+
+    /// Returns the frequency of uplink channel `channel`, which must be below [channel_count].
+    /// [tx_channel] checks this.
+    ///
+    /// [channel_count]: RegionalParameters::channel_count
+    /// [tx_channel]: RegionalParameters::tx_channel
+    fn tx_frequency(&self, channel: usize) -> Hz;
+
+    fn rx1_frequency(&self, channel: usize) -> Hz;
+
+    fn rx2_frequency(&self) -> Hz;
+
+    fn rx2_data_rate(&self) -> usize;
+
+    /// Returns data rate `dr`, or `None` if it is reserved or unknown.
+    fn data_rate(&self, dr: usize) -> Option<&DataRate>;
+
+    fn coding_rate(&self) -> CodingRate;
+
+    fn long_interleaving(&self) -> bool;
+
+    fn max_tx_data_rate(&self) -> usize;
+
+    fn max_eirp(&self) -> i8;
+
+    fn tx_powers(&self) -> &[u8];
+
+    fn max_mac_payload_size(&self, dr: usize, dwell_time: bool) -> usize;
+
+    fn uplink_dwell_time(&self) -> bool;
+
+    fn downlink_dwell_time(&self) -> bool;
+
+    fn tx_param_setup(&self) -> bool;
+
+    fn join_data_rate(&self) -> usize;
+
+    fn rx1_data_rate(&self, tx_dr: usize, rx1_dr_offset: usize, dwell_time: bool) -> Option<usize>;
+
+    fn beacon(&self) -> &BeaconParameters<'_>;
+
+    fn duty_cycle_bands(&self) -> &[DutyCycleBand];
+
+    fn cf_list_type(&self) -> CfListType;
+
+    fn sub_bands(&self) -> &[ChannelMask];
+
+    fn listen_before_talk(&self) -> Option<&ListenBeforeTalk>;
+
+    fn supports_data_rate(&self, channel: usize, dr: usize) -> bool;
+
+    fn default_channel_mask(&self) -> ChannelMask;
+
+    fn join_channel_mask(&self) -> ChannelMask;
+
+    /// Picks one of the channels enabled in `mask` that supports data rate `dr`, using `noise` to
+    /// spread uplinks over them.
+    fn select_channel(&self, dr: usize, mask: &ChannelMask, noise: usize) -> Option<usize> {
+        let channels = || {
+            mask.channels()
+                .take_while(|&channel| channel < self.channel_count())
+                .filter(move |&channel| self.supports_data_rate(channel, dr))
+        };
+        let count = channels().count();
+        if count == 0 {
+            None
+        } else {
+            channels().nth(noise % count)
+        }
+    }
+
+    /// Returns the channel for `data_rate` at `frequency`, with the coding rate of the region.
+    fn channel(&self, data_rate: &DataRate, frequency: Hz) -> ChannelConfig {
+        match data_rate.channel(frequency, self.coding_rate()) {
+            ChannelConfig::LoRa(channel) if self.long_interleaving() => {
+                ChannelConfig::LongInterleaving(channel)
+            }
+            channel => channel,
+        }
+    }
+
+    /// Returns the channel to transmit on at data rate `dr` on uplink channel `channel`, or `None`
+    /// if the region has no such channel.
+    fn tx_channel(&self, dr: usize, channel: usize) -> Option<ChannelConfig> {
+        if channel >= self.channel_count() {
+            return None;
+        }
+        let data_rate = self.data_rate(dr)?;
+        Some(self.channel(data_rate, self.tx_frequency(channel)))
+    }
+
+    /// Returns the channel of RX1 at data rate `dr`, after an uplink on channel `channel`. Data
+    /// rates that are only used for uplinks, and channels the region does not have, return `None`.
+    fn rx1_channel(&self, dr: usize, channel: usize) -> Option<ChannelConfig> {
+        if channel >= self.channel_count() {
+            return None;
+        }
+        let data_rate = self.data_rate(dr).filter(|dr| !dr.is_uplink_only())?;
+        Some(self.channel(data_rate, self.rx1_frequency(channel)))
+    }
+
+    /// Returns the channel of RX2 at data rate `dr`. Data rates that are only used for uplinks
+    /// return `None`.
+    fn rx2_channel(&self, dr: usize) -> Option<ChannelConfig> {
+        let data_rate = self.data_rate(dr).filter(|dr| !dr.is_uplink_only())?;
+        Some(self.channel(data_rate, self.rx2_frequency()))
+    }
+}
+
+impl<R: Region> RegionalParameters for R {
+    fn channel_count(&self) -> usize {
+        R::channel_count()
+    }
+
+    fn tx_frequency(&self, channel: usize) -> Hz {
+        R::tx_frequency(channel)
+    }
+
+    fn rx1_frequency(&self, channel: usize) -> Hz {
+        R::rx1_frequency(channel)
+    }
+
+    fn rx2_frequency(&self) -> Hz {
+        R::RX2_FREQUENCY
+    }
+
+    fn rx2_data_rate(&self) -> usize {
+        R::RX2_DATA_RATE
+    }
+
+    fn data_rate(&self, dr: usize) -> Option<&DataRate> {
+        R::DATA_RATES.get(dr).and_then(Option::as_ref)
+    }
+
+    fn coding_rate(&self) -> CodingRate {
+        R::CODING_RATE
+    }
+
+    fn long_interleaving(&self) -> bool {
+        R::LONG_INTERLEAVING
+    }
+
+    fn max_tx_data_rate(&self) -> usize {
+        R::MAX_TX_DATA_RATE
+    }
+
+    fn max_eirp(&self) -> i8 {
+        R::MAX_EIRP
+    }
+
+    fn tx_powers(&self) -> &[u8] {
+        R::TX_POWERS
+    }
+
+    fn max_mac_payload_size(&self, dr: usize, dwell_time: bool) -> usize {
+        R::max_mac_payload_size(dr, dwell_time)
+    }
+
+    fn uplink_dwell_time(&self) -> bool {
+        R::UPLINK_DWELL_TIME
+    }
+
+    fn downlink_dwell_time(&self) -> bool {
+        R::DOWNLINK_DWELL_TIME
+    }
+
+    fn tx_param_setup(&self) -> bool {
+        R::TX_PARAM_SETUP
+    }
+
+    fn join_data_rate(&self) -> usize {
+        R::JOIN_DATA_RATE
+    }
+
+    fn rx1_data_rate(&self, tx_dr: usize, rx1_dr_offset: usize, dwell_time: bool) -> Option<usize> {
+        R::rx1_data_rate(tx_dr, rx1_dr_offset, dwell_time)
+    }
+
+    fn beacon(&self) -> &BeaconParameters<'_> {
+        &R::BEACON
+    }
+
+    fn duty_cycle_bands(&self) -> &[DutyCycleBand] {
+        R::DUTY_CYCLE_BANDS
+    }
+
+    fn cf_list_type(&self) -> CfListType {
+        R::CF_LIST_TYPE
+    }
+
+    fn sub_bands(&self) -> &[ChannelMask] {
+        R::SUB_BANDS
+    }
+
+    fn listen_before_talk(&self) -> Option<&ListenBeforeTalk> {
+        R::LISTEN_BEFORE_TALK.as_ref()
+    }
+
+    fn supports_data_rate(&self, channel: usize, dr: usize) -> bool {
+        R::supports_data_rate(channel, dr)
+    }
+
+    fn default_channel_mask(&self) -> ChannelMask {
+        R::default_channel_mask()
+    }
+
+    fn join_channel_mask(&self) -> ChannelMask {
+        R::join_channel_mask()
+    }
+}
+
+/// Implements the required methods of [RegionalParameters] by forwarding them to `$target`, an
+/// expression of `$this` (which must be `self`) that implements it.
+macro_rules! forward_regional_parameters {
+    ($this:ident => $target:expr) => {
+        fn channel_count(&$this) -> usize {
+            $target.channel_count()
+        }
+
+        fn tx_frequency(&$this, channel: usize) -> Hz {
+            $target.tx_frequency(channel)
+        }
+
+        fn rx1_frequency(&$this, channel: usize) -> Hz {
+            $target.rx1_frequency(channel)
+        }
+
+        fn rx2_frequency(&$this) -> Hz {
+            $target.rx2_frequency()
+        }
+
+        fn rx2_data_rate(&$this) -> usize {
+            $target.rx2_data_rate()
+        }
+
+        fn data_rate(&$this, dr: usize) -> Option<&DataRate> {
+            $target.data_rate(dr)
+        }
+
+        fn coding_rate(&$this) -> CodingRate {
+            $target.coding_rate()
+        }
+
+        fn long_interleaving(&$this) -> bool {
+            $target.long_interleaving()
+        }
+
+        fn max_tx_data_rate(&$this) -> usize {
+            $target.max_tx_data_rate()
+        }
+
+        fn max_eirp(&$this) -> i8 {
+            $target.max_eirp()
+        }
+
+        fn tx_powers(&$this) -> &[u8] {
+            $target.tx_powers()
+        }
+
+        fn max_mac_payload_size(&$this, dr: usize, dwell_time: bool) -> usize {
+            $target.max_mac_payload_size(dr, dwell_time)
+        }
+
+        fn uplink_dwell_time(&$this) -> bool {
+            $target.uplink_dwell_time()
+        }
+
+        fn downlink_dwell_time(&$this) -> bool {
+            $target.downlink_dwell_time()
+        }
+
+        fn tx_param_setup(&$this) -> bool {
+            $target.tx_param_setup()
+        }
+
+        fn join_data_rate(&$this) -> usize {
+            $target.join_data_rate()
+        }
+
+        fn rx1_data_rate(
+            &$this,
+            tx_dr: usize,
+            rx1_dr_offset: usize,
+            dwell_time: bool,
+        ) -> Option<usize> {
+            $target.rx1_data_rate(tx_dr, rx1_dr_offset, dwell_time)
+        }
+
+        fn beacon(&$this) -> &BeaconParameters<'_> {
+            $target.beacon()
+        }
+
+        fn duty_cycle_bands(&$this) -> &[DutyCycleBand] {
+            $target.duty_cycle_bands()
+        }
+
+        fn cf_list_type(&$this) -> CfListType {
+            $target.cf_list_type()
+        }
+
+        fn sub_bands(&$this) -> &[ChannelMask] {
+            $target.sub_bands()
+        }
+
+        fn listen_before_talk(&$this) -> Option<&ListenBeforeTalk> {
+            $target.listen_before_talk()
+        }
+
+        fn supports_data_rate(&$this, channel: usize, dr: usize) -> bool {
+            $target.supports_data_rate(channel, dr)
+        }
+
+        fn default_channel_mask(&$this) -> ChannelMask {
+            $target.default_channel_mask()
+        }
+
+        fn join_channel_mask(&$this) -> ChannelMask {
+            $target.join_channel_mask()
+        }
+    };
+}
+
+pub(crate) use forward_regional_parameters;
+
+impl RegionalParameters for &dyn RegionalParameters {
+    forward_regional_parameters!(self => (**self));
+}
+
+/// The sub-bands of regions with 64 + 8 uplink channels.
+const FIXED_SUB_BANDS: [ChannelMask; 8] = [
+    ChannelMask::sub_band(SubBand::Fsb1),
+    ChannelMask::sub_band(SubBand::Fsb2),
+    ChannelMask::sub_band(SubBand::Fsb3),
+    ChannelMask::sub_band(SubBand::Fsb4),
+    ChannelMask::sub_band(SubBand::Fsb5),
+    ChannelMask::sub_band(SubBand::Fsb6),
+    ChannelMask::sub_band(SubBand::Fsb7),
+    ChannelMask::sub_band(SubBand::Fsb8),
+];
+
+/// Computes the frequencies of `N` channels that are `step` apart.
+const fn channels<const N: usize>(first: Hz, step: Hz) -> [Hz; N] {
+    let mut channels = [0; N];
+    let mut i = 0;
+    while i < N {
+        channels[i] = first + step * i as Hz;
+        i += 1;
+    }
+    channels
+}
+
+/// Computes the frequencies of the 64 uplink channels of 125 kHz, which are 200 kHz apart,
+/// followed by the 8 uplink channels of 500 kHz, which are 1.6 MHz apart.
+const fn fixed_uplink_channels(first_125: Hz, first_500: Hz) -> [Hz; 72] {
+    let narrow: [Hz; 64] = channels(first_125, 200_000);
+    let wide: [Hz; 8] = channels(first_500, 1_600_000);
+    let mut channels = [0; 72];
+    let mut i = 0;
+    while i < 72 {
+        channels[i] = if i < 64 { narrow[i] } else { wide[i - 64] };
+        i += 1;
+    }
+    channels
+}
+
+/// Parameters of the Class B beacon of a region. Ping slots use the same data rate as the beacon
+/// by default.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BeaconParameters<'a> {
+    data_rate: usize,
+    frequencies: &'a [Hz],
+    ping_slot_frequencies: &'a [Hz],
+    rfu1_size: usize,
+    rfu2_size: usize,
+}
+
+impl<'a> BeaconParameters<'a> {
+    pub const fn new(
+        data_rate: usize,
+        frequencies: &'a [Hz],
+        ping_slot_frequencies: &'a [Hz],
+        rfu1_size: usize,
+        rfu2_size: usize,
+    ) -> Self {
+        BeaconParameters {
+            data_rate,
+            frequencies,
+            ping_slot_frequencies,
+            rfu1_size,
+            rfu2_size,
+        }
+    }
+
+    pub fn data_rate(&self) -> usize {
+        self.data_rate
+    }
+
+    /// The frequencies the beacon is broadcast on. If there is more than one, the beacon hops
+    /// between them.
+    pub fn frequencies(&self) -> &'a [Hz] {
+        self.frequencies
+    }
+
+    /// The default frequencies for ping slots. If there is more than one, ping slots hop between
+    /// them.
+    pub fn ping_slot_frequencies(&self) -> &'a [Hz] {
+        self.ping_slot_frequencies
+    }
+
+    /// The number of reserved bytes before the time field of the beacon.
+    pub fn rfu1_size(&self) -> usize {
+        self.rfu1_size
+    }
+
+    /// The number of reserved bytes after the gateway-specific field of the beacon.
+    pub fn rfu2_size(&self) -> usize {
+        self.rfu2_size
+    }
+
+    /// The size of the beacon in bytes: the reserved fields, the time, the gateway-specific field
+    /// and a CRC after both the time and the gateway-specific field.
+    pub fn size(&self) -> usize {
+        self.rfu1_size + 4 + 2 + 7 + self.rfu2_size + 2
+    }
+
+    /// Returns the frequency of the beacon sent at GPS time `time`.
+    pub fn frequency(&self, time: u32) -> Hz {
+        self.frequencies[Self::period(time) % self.frequencies.len()]
+    }
+
+    /// Returns the default frequency of the ping slots of device `dev_addr` in the beacon period
+    /// starting at GPS time `time`.
+    pub fn ping_slot_frequency(&self, time: u32, dev_addr: u32) -> Hz {
+        let index = (dev_addr as usize).wrapping_add(Self::period(time));
+        self.ping_slot_frequencies[index % self.ping_slot_frequencies.len()]
+    }
+
+    /// The number of beacon periods between the GPS epoch and `time`.
+    fn period(time: u32) -> usize {
+        (time / BEACON_PERIOD.as_secs() as u32) as usize
+    }
+}
+
+/// A sub-band that devices may only use for a fraction of the time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DutyCycleBand {
+    min: Hz,
+    max: Hz,
+    duty_cycle: u16,
+}
+
+impl DutyCycleBand {
+    /// Creates a band from `min` to `max` (inclusive) that may be used for one `duty_cycle`th of
+    /// the time, e.g. 100 for 1%.
+    pub const fn new(min: Hz, max: Hz, duty_cycle: u16) -> Self {
+        DutyCycleBand {
+            min,
+            max,
+            duty_cycle,
+        }
+    }
+
+    pub fn min(&self) -> Hz {
+        self.min
+    }
+
+    pub fn max(&self) -> Hz {
+        self.max
+    }
+
+    pub fn duty_cycle(&self) -> u16 {
+        self.duty_cycle
+    }
+
+    pub fn contains(&self, frequency: Hz) -> bool {
+        (self.min..=self.max).contains(&frequency)
+    }
+}
+
+/// Parameters for checking whether a channel is free before transmitting.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ListenBeforeTalk {
+    threshold: i16,
+    duration: Duration,
+}
+
+impl ListenBeforeTalk {
+    pub const fn new(threshold: i16, duration: Duration) -> Self {
+        ListenBeforeTalk {
+            threshold,
+            duration,
+        }
+    }
+
+    /// The RSSI in dBm at or above which a channel is considered busy.
+    pub fn threshold(&self) -> i16 {
+        self.threshold
+    }
+
+    /// How long the channel must be free before transmitting.
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+}
+
+/// The contents of the optional CFList in a join-accept.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CfListType {
+    /// Up to five additional channel frequencies.
+    Frequencies,
+    /// Channel masks enabling or disabling the fixed channels of the region.
+    ChannelMask,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks rows of the RX1 data rate table of `region`, given as (uplink DR, RX1 DR offset,
+    /// RX1 DR). `None` means that the combination is not allowed.
+    pub(super) fn assert_rx1_data_rates(
+        region: &dyn RegionalParameters,
+        dwell_time: bool,
+        rows: &[(usize, usize, Option<usize>)],
+    ) {
+        for &(tx_dr, rx1_dr_offset, rx1_dr) in rows {
+            assert_eq!(
+                region.rx1_data_rate(tx_dr, rx1_dr_offset, dwell_time),
+                rx1_dr,
+                "DR{} with offset {}",
+                tx_dr,
+                rx1_dr_offset
+            );
+        }
+    }
+
+    /// Checks that the default channels of `region` allow up to DR5, and that no default channel is
+    /// selected for the data rates in `higher`.
+    pub(super) fn assert_default_channels_up_to_dr5(
+        region: &dyn RegionalParameters,
+        higher: &[usize],
+    ) {
+        let mask = region.default_channel_mask();
+        assert!(region.supports_data_rate(1, 5));
+        assert_eq!(region.select_channel(5, &mask, 1), Some(1));
+        for &dr in higher {
+            assert!(!region.supports_data_rate(0, dr), "DR{}", dr);
+            assert_eq!(region.select_channel(dr, &mask, 0), None, "DR{}", dr);
+        }
+    }
+
+    /// Checks that `region` answers in RX1 on the frequency of each of the default `channels`, and
+    /// its RX2 frequency and data rate and beacon frequencies.
+    pub(super) fn assert_answers_on_uplink_channel(
+        region: &dyn RegionalParameters,
+        channels: &[Hz],
+        rx2: (Hz, usize),
+        beacon: &[Hz],
+    ) {
+        for (channel, &frequency) in channels.iter().enumerate() {
+            assert_eq!(
+                region.tx_frequency(channel),
+                frequency,
+                "channel {}",
+                channel
+            );
+            assert_eq!(
+                region.rx1_frequency(channel),
+                frequency,
+                "channel {}",
+                channel
+            );
+        }
+        assert_eq!((region.rx2_frequency(), region.rx2_data_rate()), rx2);
+        assert_eq!(region.beacon().frequencies(), beacon);
+    }
+
+    #[test]
+    fn subtracts_rx1_offset_from_data_rate() {
+        assert_rx1_data_rates(
+            &EU868,
+            false,
+            &[
+                (0, 0, Some(0)),
+                (0, 5, Some(0)),
+                (2, 1, Some(1)),
+                (2, 3, Some(0)),
+                (5, 0, Some(5)),
+                (5, 2, Some(3)),
+                (5, 5, Some(0)),
+                (7, 5, Some(2)),
+                (3, 6, None),
+                (12, 0, None),
+            ],
+        );
+    }
+
+    #[test]
+    fn has_no_channels_beyond_channel_count() {
+        let regions: [&dyn RegionalParameters; 3] = [&EU868, &US915, &CN470_26A];
+        for region in regions {
+            let last = region.channel_count() - 1;
+            assert!(region.tx_channel(0, last).is_some());
+            assert!(region.rx1_channel(0, last).is_some());
+            assert_eq!(region.tx_channel(0, last + 1), None);
+            assert_eq!(region.rx1_channel(0, last + 1), None);
+        }
+    }
+
+    #[test]
+    fn beacon_hops_between_frequencies() {
+        let params = US915.beacon();
+        assert_eq!(params.size(), 23);
+        // 0xCC020000 is beacon period 26739712
+        assert_eq!(params.frequency(0xCC020000), 923_300_000);
+        assert_eq!(params.frequency(0xCC020000 + 128), 923_900_000);
+        assert_eq!(params.frequency(0xCC020000 + 7 * 128), 927_500_000);
+    }
+
+    #[test]
+    fn ping_slots_hop_between_frequencies() {
+        let params = US915.beacon();
+        assert_eq!(
+            params.ping_slot_frequency(0xCC020000, 0x26011BDA),
+            924_500_000
+        );
+        assert_eq!(
+            params.ping_slot_frequency(0xCC020000 + 128, 0x26011BDA),
+            925_100_000
+        );
+        assert_eq!(params.ping_slot_frequency(0, 7), 927_500_000);
+
+        let params = EU868.beacon();
+        assert_eq!(params.size(), 17);
+        assert_eq!(
+            params.ping_slot_frequency(0xCC020000, 0x26011BDA),
+            869_525_000
+        );
+    }
 
-    fn get_data_rate<'a, ERR>(index: usize) -> Result<&'a DataRate<Self>, RadioError<ERR>> {
-        Self::DATA_RATES
-            .get(index)
-            .ok_or(RadioError::UnsupportedDataRate)
+    #[test]
+    fn beacon_channel_has_implicit_header() {
+        let params = EU868.beacon();
+        let data_rate = EU868.data_rate(params.data_rate()).unwrap();
+        let channel = data_rate.beacon_channel(869_525_000, params.size());
+        assert!(matches!(
+            channel,
+            Some(ChannelConfig::Beacon(beacon))
+                if beacon.lora.freq_khz == 869_525
+                    && beacon.length == 17
+                    && beacon.preamble_symbols == 10
+        ));
+        assert_eq!(
+            data_rate.beacon_air_time(params.size()),
+            Some(Duration::from_micros(152_576))
+        );
     }
 }
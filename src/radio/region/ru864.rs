@@ -0,0 +1,92 @@
+use radio::modulation::lora::SpreadingFactor;
+
+use crate::radio::{BeaconParameters, CfListType, DataRate, DutyCycleBand, Hz, Region};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RU864;
+
+impl Region for RU864 {
+    const JOIN_FREQUENCIES: &'static [Hz] = &[868_900_000, 869_100_000];
+
+    const TX_FREQUENCIES: &'static [Hz] = Self::JOIN_FREQUENCIES;
+
+    const RX1_FREQUENCIES: &'static [Hz] = Self::TX_FREQUENCIES;
+
+    const RX2_FREQUENCY: Hz = 869_100_000;
+
+    const RX2_DATA_RATE: usize = 0;
+
+    const DATA_RATES: &'static [Option<DataRate>] = &[
+        Some(DataRate::new(SpreadingFactor::Sf12, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf11, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf10, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf9, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf8, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf7, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf7, 250_000)),
+        Some(DataRate::fsk(50_000)),
+    ];
+
+    const MAX_TX_DATA_RATE: usize = 7;
+
+    const MAX_EIRP: i8 = 16;
+
+    const TX_POWERS: &'static [u8] = &[0, 2, 4, 6, 8, 10, 12, 14];
+
+    const MAX_PAYLOAD_SIZES: &'static [u8] = &[59, 59, 59, 123, 250, 250, 250, 250];
+
+    const RX1_DR_OFFSETS: &'static [&'static [usize]] = &[
+        &[0, 0, 0, 0, 0, 0],
+        &[1, 0, 0, 0, 0, 0],
+        &[2, 1, 0, 0, 0, 0],
+        &[3, 2, 1, 0, 0, 0],
+        &[4, 3, 2, 1, 0, 0],
+        &[5, 4, 3, 2, 1, 0],
+        &[6, 5, 4, 3, 2, 1],
+        &[7, 6, 5, 4, 3, 2],
+    ];
+
+    const BEACON: BeaconParameters<'static> =
+        BeaconParameters::new(3, &[869_100_000], &[869_100_000], 2, 0);
+
+    const DUTY_CYCLE_BANDS: &'static [DutyCycleBand] = &[
+        DutyCycleBand::new(864_000_000, 865_000_000, 1000),
+        DutyCycleBand::new(866_000_000, 868_000_000, 100),
+        DutyCycleBand::new(868_700_000, 869_200_000, 1000),
+        DutyCycleBand::new(869_400_000, 869_650_000, 10),
+        DutyCycleBand::new(869_700_000, 870_000_000, 100),
+    ];
+
+    const CF_LIST_TYPE: CfListType = CfListType::Frequencies;
+
+    /// The two default channels only support DR0 to DR5.
+    fn supports_data_rate(_channel: usize, dr: usize) -> bool {
+        dr <= 5
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::radio::region::tests::assert_default_channels_up_to_dr5;
+    use crate::radio::RegionalParameters;
+
+    #[test]
+    fn has_two_default_channels() {
+        assert_eq!(RU864.channel_count(), 2);
+        assert_eq!(RU864.tx_frequency(0), 868_900_000);
+        assert_eq!(RU864.tx_frequency(1), 869_100_000);
+        assert_eq!(RU864.rx1_frequency(1), 869_100_000);
+        assert_eq!(
+            (RU864.rx2_frequency(), RU864.rx2_data_rate()),
+            (869_100_000, 0)
+        );
+        assert_eq!(RU864.beacon().frequencies(), &[869_100_000]);
+    }
+
+    #[test]
+    fn limits_default_channels_to_dr5() {
+        assert_default_channels_up_to_dr5(&RU864, &[6, 7]);
+    }
+}
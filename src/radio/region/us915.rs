@@ -0,0 +1,194 @@
+use radio::modulation::lora::SpreadingFactor;
+
+use crate::radio::region::{channels, fixed_uplink_channels, FIXED_SUB_BANDS};
+use crate::radio::{
+    BeaconParameters, CfListType, ChannelMask, DataRate, DutyCycleBand, Hz, LrFhssCodingRate,
+    Region,
+};
+
+/// The US902-928 region. It has 64 uplink channels of 125 kHz, 8 uplink channels of 500 kHz and
+/// 8 downlink channels of 500 kHz. Most gateways only listen on one [sub-band], so devices should
+/// join with [join_with_channels] or [join_scan].
+///
+/// All uplinks are subject to a dwell time of 400 ms, which the maximum payload sizes already
+/// account for.
+///
+/// [sub-band]: crate::radio::ChannelMask::sub_band
+/// [join_with_channels]: crate::device::Device::join_with_channels
+/// [join_scan]: crate::device::Device::join_scan
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct US915;
+
+const UPLINK_CHANNELS: [Hz; 72] = fixed_uplink_channels(902_300_000, 903_000_000);
+
+const DOWNLINK_CHANNELS: [Hz; 8] = channels(923_300_000, 600_000);
+
+impl Region for US915 {
+    const JOIN_FREQUENCIES: &'static [Hz] = Self::TX_FREQUENCIES;
+
+    const TX_FREQUENCIES: &'static [Hz] = &UPLINK_CHANNELS;
+
+    const RX1_FREQUENCIES: &'static [Hz] = &DOWNLINK_CHANNELS;
+
+    const RX2_FREQUENCY: Hz = 923_300_000;
+
+    const RX2_DATA_RATE: usize = 8;
+
+    const DATA_RATES: &'static [Option<DataRate>] = &[
+        Some(DataRate::new(SpreadingFactor::Sf10, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf9, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf8, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf7, 125_000)),
+        Some(DataRate::new(SpreadingFactor::Sf8, 500_000)),
+        Some(DataRate::lr_fhss(
+            LrFhssCodingRate::Cr1_3,
+            1_523_000,
+            25_400,
+        )),
+        Some(DataRate::lr_fhss(
+            LrFhssCodingRate::Cr2_3,
+            1_523_000,
+            25_400,
+        )),
+        None,
+        Some(DataRate::new(SpreadingFactor::Sf12, 500_000)),
+        Some(DataRate::new(SpreadingFactor::Sf11, 500_000)),
+        Some(DataRate::new(SpreadingFactor::Sf10, 500_000)),
+        Some(DataRate::new(SpreadingFactor::Sf9, 500_000)),
+        Some(DataRate::new(SpreadingFactor::Sf8, 500_000)),
+        Some(DataRate::new(SpreadingFactor::Sf7, 500_000)),
+    ];
+
+    const MAX_TX_DATA_RATE: usize = 6;
+
+    const MAX_EIRP: i8 = 30;
+
+    const TX_POWERS: &'static [u8] = &[0, 2, 4, 6, 8, 10, 12, 14, 16, 18, 20, 22, 24, 26, 28];
+
+    const MAX_PAYLOAD_SIZES: &'static [u8] = &[
+        19, 61, 133, 250, 250, 58, 133, 0, 61, 137, 250, 250, 250, 250,
+    ];
+
+    const RX1_DR_OFFSETS: &'static [&'static [usize]] = &[
+        &[10, 9, 8, 8],
+        &[11, 10, 9, 8],
+        &[12, 11, 10, 9],
+        &[13, 12, 11, 10],
+        &[13, 13, 12, 11],
+        &[10, 9, 8, 8],
+        &[11, 10, 9, 8],
+    ];
+
+    const BEACON: BeaconParameters<'static> =
+        BeaconParameters::new(8, &DOWNLINK_CHANNELS, &DOWNLINK_CHANNELS, 5, 3);
+
+    const DUTY_CYCLE_BANDS: &'static [DutyCycleBand] = &[];
+
+    const CF_LIST_TYPE: CfListType = CfListType::ChannelMask;
+
+    const SUB_BANDS: &'static [ChannelMask] = &FIXED_SUB_BANDS;
+
+    fn supports_data_rate(channel: usize, dr: usize) -> bool {
+        match channel {
+            0..=63 => dr <= 3,
+            // The 500 kHz channels also carry LR-FHSS
+            _ => (4..=6).contains(&dr),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::radio::region::tests::assert_rx1_data_rates;
+    use crate::radio::{ChannelConfig, RegionalParameters, SubBand};
+
+    #[test]
+    fn has_fixed_channels() {
+        assert_eq!(US915.channel_count(), 72);
+        let channels = [
+            (0, 902_300_000, 923_300_000),
+            (7, 903_700_000, 927_500_000),
+            (8, 903_900_000, 923_300_000),
+            (63, 914_900_000, 927_500_000),
+            (64, 903_000_000, 923_300_000),
+            (71, 914_200_000, 927_500_000),
+        ];
+        for (channel, tx, rx1) in channels {
+            assert_eq!(US915.tx_frequency(channel), tx, "channel {}", channel);
+            assert_eq!(US915.rx1_frequency(channel), rx1, "channel {}", channel);
+        }
+        assert_eq!(
+            (US915.rx2_frequency(), US915.rx2_data_rate()),
+            (923_300_000, 8)
+        );
+    }
+
+    #[test]
+    fn uses_500_khz_channels_for_dr4() {
+        assert!(US915.supports_data_rate(0, 3));
+        assert!(!US915.supports_data_rate(0, 4));
+        assert!(US915.supports_data_rate(64, 4));
+        assert!(!US915.supports_data_rate(64, 3));
+        assert_eq!(
+            US915.data_rate(4),
+            Some(&DataRate::new(SpreadingFactor::Sf8, 500_000))
+        );
+        assert_eq!(US915.max_mac_payload_size(4, false), 250);
+    }
+
+    #[test]
+    fn answers_on_500_khz_data_rates() {
+        assert_eq!(
+            US915.data_rate(8),
+            Some(&DataRate::new(SpreadingFactor::Sf12, 500_000))
+        );
+        assert_eq!(
+            US915.data_rate(13),
+            Some(&DataRate::new(SpreadingFactor::Sf7, 500_000))
+        );
+        assert_rx1_data_rates(
+            &US915,
+            false,
+            &[
+                (0, 0, Some(10)),
+                (0, 3, Some(8)),
+                (3, 1, Some(12)),
+                (4, 0, Some(13)),
+                (4, 3, Some(11)),
+                (4, 4, None),
+            ],
+        );
+    }
+
+    #[test]
+    fn limits_payload_sizes() {
+        let sizes = [
+            (0, 19),
+            (1, 61),
+            (2, 133),
+            (3, 250),
+            (7, 0),
+            (8, 61),
+            (9, 137),
+            (13, 250),
+        ];
+        for (dr, m) in sizes {
+            assert_eq!(US915.max_mac_payload_size(dr, false), m, "DR{}", dr);
+        }
+    }
+
+    #[test]
+    fn selects_lr_fhss_channel() {
+        let mask = ChannelMask::sub_band(SubBand::Fsb2);
+        assert_eq!(US915.select_channel(5, &mask, 7), Some(65));
+        assert_eq!(US915.select_channel(6, &mask, 0), Some(65));
+        assert_eq!(US915.select_channel(7, &mask, 0), None);
+
+        let channel = US915.tx_channel(5, 65);
+        assert!(
+            matches!(channel, Some(ChannelConfig::LrFhss(lr_fhss)) if lr_fhss.freq_khz == 904_600)
+        );
+    }
+}
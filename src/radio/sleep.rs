@@ -0,0 +1,39 @@
+use core::time::Duration;
+
+/// How long the radio spent in each state, since it was created or last reset.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct StateDurations {
+    transmitting: Duration,
+    receiving: Duration,
+    sleeping: Duration,
+}
+
+impl StateDurations {
+    /// The time spent transmitting.
+    pub fn transmitting(&self) -> Duration {
+        self.transmitting
+    }
+
+    /// The time spent receiving, including listen-before-talk.
+    pub fn receiving(&self) -> Duration {
+        self.receiving
+    }
+
+    /// The time spent sleeping while waiting for receive windows.
+    pub fn sleeping(&self) -> Duration {
+        self.sleeping
+    }
+
+    pub(crate) fn add_transmitting(&mut self, duration: Duration) {
+        self.transmitting += duration;
+    }
+
+    pub(crate) fn add_receiving(&mut self, duration: Duration) {
+        self.receiving += duration;
+    }
+
+    pub(crate) fn add_sleeping(&mut self, duration: Duration) {
+        self.sleeping += duration;
+    }
+}
@@ -0,0 +1,167 @@
+use core::time::Duration;
+
+use crate::lorawan::{Settings, NEXT_DELAY};
+use crate::radio::{ChannelConfig, DataRate, RegionalParameters};
+
+/// The number of preamble symbols the radio must receive to detect a downlink.
+const MIN_RX_SYMBOLS: u32 = 6;
+
+/// The timing of a receive window. It is centered on the preamble of a downlink that is sent
+/// exactly on time, and is long enough to detect six preamble symbols even if the device's timing
+/// is off by up to the clock error in either direction.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RxWindow {
+    offset: Duration,
+    timeout: Duration,
+}
+
+impl RxWindow {
+    /// Computes the window for a downlink at `data_rate`, for a timing error of up to
+    /// `clock_error`.
+    pub fn new(data_rate: &DataRate, clock_error: Duration) -> Self {
+        Self::with_preamble(data_rate, data_rate.preamble_symbols(), clock_error)
+    }
+
+    /// Computes the window for a downlink at `data_rate` on `channel`. Unlike other downlinks, a
+    /// beacon has the preamble length of its [BeaconChannel].
+    ///
+    /// [BeaconChannel]: crate::radio::BeaconChannel
+    pub fn for_channel(
+        channel: &ChannelConfig,
+        data_rate: &DataRate,
+        clock_error: Duration,
+    ) -> Self {
+        match channel {
+            ChannelConfig::Beacon(beacon) => {
+                Self::with_preamble(data_rate, beacon.preamble_symbols as u32, clock_error)
+            }
+            _ => Self::new(data_rate, clock_error),
+        }
+    }
+
+    fn with_preamble(data_rate: &DataRate, preamble_symbols: u32, clock_error: Duration) -> Self {
+        let symbol_time = data_rate.symbol_time();
+        let timeout = symbol_time * 2 * MIN_RX_SYMBOLS + clock_error * 2;
+        let preamble = symbol_time * preamble_symbols;
+        let offset = (timeout / 2).saturating_sub(preamble / 2);
+
+        RxWindow { offset, timeout }
+    }
+
+    /// How much earlier than the start of the downlink the radio must start listening.
+    pub fn offset(&self) -> Duration {
+        self.offset
+    }
+
+    /// How long the radio must listen for a preamble before giving up.
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+}
+
+/// Returns the data rate and timing of RX1 and RX2 after an uplink at `tx_dr`, for an RX1 delay of
+/// `delay`. The windows are widened by `clock_error`, and by a drift of `clock_drift` parts per
+/// million over their delay.
+pub(crate) fn rx_windows<R: RegionalParameters>(
+    settings: &Settings<R>,
+    tx_dr: usize,
+    dwell_time: bool,
+    delay: Duration,
+    clock_error: Duration,
+    clock_drift: u32,
+) -> Option<[(usize, RxWindow); 2]> {
+    let error = |delay: Duration| {
+        let drift = delay.as_micros() as u64 * clock_drift as u64 / 1_000_000;
+        clock_error + Duration::from_micros(drift)
+    };
+
+    let region = settings.region();
+    let rx1_dr = region.rx1_data_rate(tx_dr, settings.rx1_dr_offset(), dwell_time)?;
+    let rx2_dr = settings.rx2_dr();
+    let rx1_window = RxWindow::new(region.data_rate(rx1_dr)?, error(delay));
+    let rx2_window = RxWindow::new(region.data_rate(rx2_dr)?, error(delay + NEXT_DELAY));
+
+    Some([(rx1_dr, rx1_window), (rx2_dr, rx2_window)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::radio::EU868;
+    use radio::modulation::lora::CodingRate;
+
+    fn window(offset_us: u64, timeout_us: u64) -> RxWindow {
+        RxWindow {
+            offset: Duration::from_micros(offset_us),
+            timeout: Duration::from_micros(timeout_us),
+        }
+    }
+
+    #[test]
+    fn centers_window_on_preamble() {
+        // The preamble is eight symbols for LoRa, and five bytes of 160 µs for FSK at DR7
+        let cases = [
+            (0, 0, window(65_536, 393_216)),
+            (0, 3_000, window(68_536, 399_216)),
+            (5, 0, window(2_048, 12_288)),
+            (6, 1_000, window(2_024, 8_144)),
+            (7, 0, window(560, 1_920)),
+        ];
+        for (dr, clock_error, expected) in cases {
+            let data_rate = EU868.data_rate(dr).unwrap();
+            let clock_error = Duration::from_micros(clock_error);
+            assert_eq!(RxWindow::new(data_rate, clock_error), expected, "DR{}", dr);
+        }
+    }
+
+    #[test]
+    fn centers_beacon_window_on_longer_preamble() {
+        // A beacon at DR3 has ten symbols of 4096 µs, instead of eight
+        let data_rate = EU868.data_rate(3).unwrap();
+        let clock_error = Duration::from_micros(1_000);
+        let beacon = data_rate.beacon_channel(869_525_000, 17).unwrap();
+        assert_eq!(
+            RxWindow::for_channel(&beacon, data_rate, clock_error),
+            window(5_096, 51_152)
+        );
+
+        let downlink = data_rate.channel(869_525_000, CodingRate::Cr4_5);
+        assert_eq!(
+            RxWindow::for_channel(&downlink, data_rate, clock_error),
+            window(9_192, 51_152)
+        );
+    }
+
+    #[test]
+    fn widens_windows_by_drift() {
+        let settings = Settings::default_for(EU868);
+        let delay = Duration::from_secs(1);
+        let cases = [
+            (
+                0,
+                0,
+                [(5, window(2_048, 12_288)), (0, window(65_536, 393_216))],
+            ),
+            (
+                0,
+                100,
+                [(5, window(2_148, 12_488)), (0, window(65_736, 393_616))],
+            ),
+            (
+                1_000,
+                100,
+                [(5, window(3_148, 14_488)), (0, window(66_736, 395_616))],
+            ),
+        ];
+        for (clock_error, clock_drift, expected) in cases {
+            let clock_error = Duration::from_micros(clock_error);
+            let windows = rx_windows(&settings, 5, false, delay, clock_error, clock_drift);
+            assert_eq!(windows, Some(expected), "{:?} {}", clock_error, clock_drift);
+        }
+        assert_eq!(
+            rx_windows(&settings, 15, false, delay, Duration::ZERO, 0),
+            None
+        );
+    }
+}